@@ -0,0 +1,188 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::decompiler::IlSpyOptions;
+use crate::parser::OnSyntaxError;
+use crate::types::Chunk;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "tsdsp",
+    version,
+    about = "Decompile a .NET assembly and lift its C# types"
+)]
+pub struct Cli {
+    /// Increase output verbosity (-v, -vv)
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    pub verbose: u8,
+
+    /// Use this directory for decompiled sources instead of a fresh temporary one
+    #[arg(long, global = true)]
+    pub workdir: Option<PathBuf>,
+
+    /// Keep the temporary work directory after the run
+    #[arg(long, global = true)]
+    pub keep: bool,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Decompile an assembly with `ilspycmd` into a directory of C# sources
+    Extract {
+        /// Assembly to decompile
+        dll: PathBuf,
+
+        /// Directory to write the decompiled project to
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        #[command(flatten)]
+        decompile: DecompileArgs,
+    },
+    /// Parse C# sources and list the extracted types
+    Parse(InputArgs),
+    /// Print counts of the extracted types by kind
+    Stats(InputArgs),
+    /// Generate Rust definitions for the extracted types
+    GenRust {
+        #[command(flatten)]
+        input: InputArgs,
+
+        /// File to write the generated code to, stdout if omitted
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Dump the intermediate representation of the extracted types
+    DumpIr {
+        #[command(flatten)]
+        input: InputArgs,
+
+        /// File to write the dump to, stdout if omitted
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct InputArgs {
+    /// Assembly to decompile, or an already decompiled source directory or .cs file
+    pub input: PathBuf,
+
+    #[command(flatten)]
+    pub filter: Filter,
+
+    #[command(flatten)]
+    pub decompile: DecompileArgs,
+
+    /// Skip files that fail to parse and print a summary of the failures
+    #[arg(long)]
+    pub keep_going: bool,
+
+    /// Write the failure summary as JSON to this file, implies --keep-going
+    #[arg(long, value_name = "PATH")]
+    pub report: Option<PathBuf>,
+
+    /// What to do with files tree-sitter reports syntax errors in
+    #[arg(long, value_enum, default_value_t = OnSyntaxError::BestEffort)]
+    pub on_syntax_error: OnSyntaxError,
+
+    /// How to print parse diagnostics on stderr
+    #[arg(long, value_enum, default_value_t = MessageFormat::Human)]
+    pub message_format: MessageFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MessageFormat {
+    /// Source line with the offending node underlined
+    Human,
+    /// One JSON object per line
+    Json,
+}
+
+#[derive(Debug, Clone, Default, Args)]
+pub struct DecompileArgs {
+    /// Path to the `ilspycmd` executable
+    #[arg(long, value_name = "PATH")]
+    pub ilspycmd: Option<PathBuf>,
+
+    /// C# language version to decompile to, e.g. CSharp7_3 or Latest
+    #[arg(long, value_name = "VERSION")]
+    pub language_version: Option<String>,
+
+    /// Use one directory per namespace segment in the decompiled project
+    #[arg(long)]
+    pub nested_directories: bool,
+
+    /// Directory to search for referenced assemblies, may be repeated
+    #[arg(short, long = "reference", value_name = "DIR")]
+    pub references: Vec<PathBuf>,
+}
+
+impl DecompileArgs {
+    pub fn options(&self) -> IlSpyOptions {
+        IlSpyOptions {
+            program: self.ilspycmd.clone(),
+            language_version: self.language_version.clone(),
+            nested_directories: self.nested_directories,
+            references: self.references.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Args)]
+pub struct Filter {
+    /// Only keep types of the given kind, may be repeated
+    #[arg(short, long = "kind", value_enum)]
+    pub kinds: Vec<ChunkKind>,
+
+    /// Only keep types whose namespace qualified name contains this string
+    #[arg(short, long)]
+    pub name: Option<String>,
+}
+
+impl Filter {
+    pub fn matches(&self, chunk: &Chunk) -> bool {
+        let kind = ChunkKind::from(chunk);
+        if !self.kinds.is_empty() && !self.kinds.contains(&kind) {
+            return false;
+        }
+        match &self.name {
+            Some(name) => chunk.full_name().contains(name.as_str()),
+            None => true,
+        }
+    }
+
+    /// Every chunk, nested ones included, that passes the filter.
+    pub fn select<'a>(&self, chunks: impl IntoIterator<Item = &'a Chunk>) -> Vec<&'a Chunk> {
+        chunks
+            .into_iter()
+            .flat_map(|x| x.flatten())
+            .filter(|x| self.matches(x))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ChunkKind {
+    Class,
+    Enum,
+    Struct,
+    Interface,
+    Delegate,
+}
+
+impl From<&Chunk> for ChunkKind {
+    fn from(chunk: &Chunk) -> Self {
+        match chunk {
+            Chunk::Class(_) => ChunkKind::Class,
+            Chunk::Enum(_) => ChunkKind::Enum,
+            Chunk::Struct(_) => ChunkKind::Struct,
+            Chunk::Interface(_) => ChunkKind::Interface,
+            Chunk::Delegate(_) => ChunkKind::Delegate,
+        }
+    }
+}
@@ -0,0 +1,361 @@
+use proc_macro2::{Ident, Literal, Span, TokenStream};
+use quote::{format_ident, quote};
+
+use crate::types::{
+    AccessorKind, Chunk, Class, Enum, Interface, Member, Method, Modifier, Parameter,
+    ParameterModifier, Primitive, Struct, Type, TypeParameter,
+};
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+    "pub", "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "abstract", "become", "do", "final", "macro", "override", "priv", "typeof", "unsized",
+    "virtual", "yield", "try",
+];
+
+/// Nested chunks are not descended into, pass them in flattened.
+pub fn generate(chunks: &[&Chunk]) -> TokenStream {
+    chunks.iter().map(|x| gen_chunk(x)).collect()
+}
+
+fn gen_chunk(chunk: &Chunk) -> TokenStream {
+    match chunk {
+        Chunk::Class(x) => gen_class(x),
+        Chunk::Enum(x) => gen_enum(x),
+        Chunk::Struct(x) => gen_struct(x),
+        Chunk::Interface(x) => gen_interface(x),
+        Chunk::Delegate(x) => gen_delegate(x),
+    }
+}
+
+fn gen_class(class: &Class) -> TokenStream {
+    let name = ident(class.name());
+    let generics = gen_generics(class.type_parameters());
+    let fields = gen_fields(&class.members());
+    quote! {
+        #[derive(Debug, Clone)]
+        pub struct #name #generics {
+            #fields
+        }
+    }
+}
+
+fn gen_struct(st: &Struct) -> TokenStream {
+    let name = ident(st.name());
+    let generics = gen_generics(st.type_parameters());
+    let fields = gen_fields(&st.members());
+    quote! {
+        #[derive(Debug, Clone)]
+        pub struct #name #generics {
+            #fields
+        }
+    }
+}
+
+/// Instance fields, plus auto-properties since those carry a backing field,
+/// in declaration order.
+fn gen_fields(members: &[Member]) -> TokenStream {
+    members
+        .iter()
+        .filter_map(|x| match x {
+            Member::Variable(x) if !is_static(x.modifiers()) => Some((x.name(), x.type_())),
+            Member::Property(x) if x.is_auto() && !is_static(x.modifiers()) => {
+                Some((x.name(), x.type_()))
+            }
+            _ => None,
+        })
+        .map(|(name, ty)| {
+            let name = ident(name);
+            let ty = gen_type(ty);
+            quote! { pub #name: #ty, }
+        })
+        .collect()
+}
+
+fn is_static(modifiers: &[Modifier]) -> bool {
+    modifiers
+        .iter()
+        .any(|m| matches!(m, Modifier::Static | Modifier::Const))
+}
+
+/// Members sharing a value with an earlier one become associated consts, as
+/// Rust rejects duplicate discriminants. Members whose value is unknown are
+/// left out and listed in the docs rather than numbered by Rust.
+fn gen_enum(en: &Enum) -> TokenStream {
+    let name = ident(en.name());
+    let mut variants = TokenStream::new();
+    let mut aliases = TokenStream::new();
+    let mut unknown = Vec::new();
+    let mut seen: Vec<(i128, Ident)> = Vec::new();
+    for x in en.members() {
+        let variant = ident(x.name());
+        let Some(v) = x.value() else {
+            unknown.push(format!("`{}`", x.name()));
+            continue;
+        };
+        match seen.iter().find(|(y, _)| *y == v) {
+            Some((_, first)) => {
+                aliases.extend(quote! { pub const #variant: #name = #name::#first; });
+            }
+            None => {
+                let value = Literal::i128_unsuffixed(v);
+                variants.extend(quote! { #variant = #value, });
+                seen.push((v, variant));
+            }
+        }
+    }
+
+    let doc = (!unknown.is_empty()).then(|| {
+        let doc = format!(
+            " Left out, their values depend on constants declared elsewhere: {}.",
+            unknown.join(", ")
+        );
+        quote! { #[doc = #doc] }
+    });
+    // A zero-variant enum cannot have a `repr`.
+    let repr = (!seen.is_empty()).then(|| {
+        let repr = gen_primitive(en.underlying());
+        quote! { #[repr(#repr)] }
+    });
+    let aliases = (!aliases.is_empty()).then(|| {
+        quote! {
+            #[allow(non_upper_case_globals)]
+            impl #name {
+                #aliases
+            }
+        }
+    });
+    quote! {
+        #doc
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #repr
+        pub enum #name {
+            #variants
+        }
+        #aliases
+    }
+}
+
+fn gen_interface(interface: &Interface) -> TokenStream {
+    let name = ident(interface.name());
+    let items = interface
+        .members()
+        .iter()
+        .map(|x| match x {
+            Member::Property(x) => {
+                let getter = ident(x.name());
+                let setter = ident(&format!("set_{}", x.name()));
+                let ty = gen_type(x.type_());
+                let get = x
+                    .accessor(AccessorKind::Get)
+                    .map(|_| quote! { fn #getter(&self) -> #ty; });
+                let set = x
+                    .accessor(AccessorKind::Set)
+                    .map(|_| quote! { fn #setter(&mut self, value: #ty); });
+                quote! { #get #set }
+            }
+            Member::Method(x) => {
+                let name = ident(x.name());
+                let generics = gen_generics(x.type_parameters());
+                let params = gen_params(x);
+                let ret = gen_return(x.return_type());
+                quote! { fn #name #generics(&self, #params) #ret; }
+            }
+            _ => TokenStream::new(),
+        })
+        .collect::<TokenStream>();
+    let generics = gen_generics(interface.type_parameters());
+    quote! {
+        pub trait #name #generics {
+            #items
+        }
+    }
+}
+
+fn gen_delegate(delegate: &Method) -> TokenStream {
+    let name = ident(delegate.name());
+    let params = delegate
+        .parameters()
+        .iter()
+        .map(|x| {
+            let ty = gen_param_type(x);
+            quote! { #ty, }
+        })
+        .collect::<TokenStream>();
+    let ret = gen_return(delegate.return_type());
+    let generics = gen_generics(delegate.type_parameters());
+    quote! {
+        pub type #name #generics = fn(#params) #ret;
+    }
+}
+
+/// Constraints have no direct Rust equivalent and are left out.
+fn gen_generics(type_parameters: &[TypeParameter]) -> TokenStream {
+    if type_parameters.is_empty() {
+        return TokenStream::new();
+    }
+    let names = type_parameters.iter().map(|x| ident(x.name()));
+    quote! { <#(#names),*> }
+}
+
+fn gen_params(method: &Method) -> TokenStream {
+    method
+        .parameters()
+        .iter()
+        .map(|x| {
+            let name = ident(x.name());
+            let ty = gen_param_type(x);
+            quote! { #name: #ty, }
+        })
+        .collect()
+}
+
+fn gen_param_type(param: &Parameter) -> TokenStream {
+    let ty = gen_type(param.type_());
+    if param.has_modifier(ParameterModifier::Ref) || param.has_modifier(ParameterModifier::Out) {
+        quote! { &mut #ty }
+    } else if param.has_modifier(ParameterModifier::In) {
+        quote! { &#ty }
+    } else {
+        ty
+    }
+}
+
+fn gen_return(ty: &Type) -> TokenStream {
+    match ty {
+        Type::Primitive(Primitive::Void) => TokenStream::new(),
+        _ => {
+            let ty = gen_type(ty);
+            quote! { -> #ty }
+        }
+    }
+}
+
+fn gen_type(ty: &Type) -> TokenStream {
+    match ty {
+        Type::Primitive(p) => gen_primitive(p),
+        Type::Array(ty, rank) => {
+            let mut res = gen_type(ty);
+            for _ in 0..*rank {
+                res = quote! { Vec<#res> };
+            }
+            res
+        }
+        Type::Tuple(elements) => {
+            let types = elements.iter().map(|x| gen_type(x.type_()));
+            quote! { (#(#types,)*) }
+        }
+        Type::Nullable(ty) => {
+            let ty = gen_type(ty);
+            quote! { Option<#ty> }
+        }
+        Type::Pointer(ty, depth) => {
+            let mut res = gen_type(ty);
+            for _ in 0..*depth {
+                res = quote! { *mut #res };
+            }
+            res
+        }
+        Type::FunctionPointer(parameters, ret) => {
+            let parameters = parameters.iter().map(gen_type);
+            let ret = gen_return(ret);
+            quote! { unsafe fn(#(#parameters),*) #ret }
+        }
+        Type::FixedBuffer(ty, len) => {
+            let ty = gen_type(ty);
+            let len = Literal::usize_unsuffixed(*len);
+            quote! { [#ty; #len] }
+        }
+        Type::Reference(ty) => {
+            let ty = gen_type(ty);
+            quote! { &mut #ty }
+        }
+        Type::Object(name, arguments) => {
+            let path = name.split('.').map(ident);
+            if arguments.is_empty() {
+                quote! { #(#path)::* }
+            } else {
+                let arguments = arguments.iter().map(gen_type);
+                quote! { #(#path)::*<#(#arguments),*> }
+            }
+        }
+    }
+}
+
+fn gen_primitive(primitive: &Primitive) -> TokenStream {
+    match primitive {
+        Primitive::Void => quote! { () },
+        Primitive::Bool => quote! { bool },
+        Primitive::Short => quote! { i16 },
+        Primitive::Ushort => quote! { u16 },
+        Primitive::Int => quote! { i32 },
+        Primitive::Uint => quote! { u32 },
+        Primitive::Long => quote! { i64 },
+        Primitive::Ulong => quote! { u64 },
+        Primitive::Float => quote! { f32 },
+        Primitive::Double => quote! { f64 },
+        Primitive::String => quote! { String },
+        Primitive::Byte => quote! { u8 },
+        Primitive::Sbyte => quote! { i8 },
+        Primitive::Char => quote! { u16 },
+        Primitive::Object | Primitive::Dynamic => quote! { Box<dyn std::any::Any> },
+        // Same size as `System.Decimal`, the arithmetic is left to the caller.
+        Primitive::Decimal => quote! { [u32; 4] },
+        Primitive::Nint => quote! { isize },
+        Primitive::Nuint => quote! { usize },
+    }
+}
+
+fn ident(name: &str) -> Ident {
+    let mut name = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    match name.as_str() {
+        "self" | "Self" | "super" | "crate" => format_ident!("{}_", name),
+        s if RUST_KEYWORDS.contains(&s) => Ident::new_raw(s, Span::call_site()),
+        s => Ident::new(s, Span::call_site()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decompiler::Fixture;
+    use crate::tests::{find, parse};
+
+    fn generate_one(source: &str, name: &str) -> String {
+        let units = parse(&Fixture::new().with_file("A.cs", source)).unwrap();
+        gen_chunk(find(&units, name)).to_string()
+    }
+
+    #[test]
+    fn enum_aliases_become_consts() {
+        let res = generate_one("enum E : byte { A, B, C = A }", "E");
+        assert!(res.contains("# [repr (u8)] pub enum E { A = 0 , B = 1 , }"));
+        assert!(res.contains("impl E { pub const C : E = E :: A ; }"));
+    }
+
+    #[test]
+    fn enum_members_of_unknown_value_are_left_out() {
+        let res = generate_one("enum E { A, B = Other.Value, C }", "E");
+        assert!(res.contains("pub enum E { A = 0 , }"));
+        assert!(res.contains("`B`, `C`"));
+    }
+
+    #[test]
+    fn empty_enums_have_no_repr() {
+        let res = generate_one("enum E : long {}", "E");
+        assert!(!res.contains("repr"));
+    }
+}
@@ -0,0 +1,196 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Context, Result, anyhow};
+
+/// Turns an input into a tree of C# sources that can be handed to the parser.
+pub trait Decompiler {
+    /// Human readable name of the backend, including its version if known.
+    fn name(&self) -> String;
+
+    /// Whether `decompile` writes into the output directory, or just points
+    /// back at existing sources.
+    fn needs_work_dir(&self) -> bool {
+        true
+    }
+
+    /// Decompile `input` into `output` and return the root of the sources.
+    fn decompile(&self, input: &Path, output: &Path) -> Result<PathBuf>;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IlSpyOptions {
+    /// Path to the `ilspycmd` executable, looked up on `PATH` if unset.
+    pub program: Option<PathBuf>,
+    /// C# language version passed through `-lv`, e.g. `CSharp7_3` or `Latest`.
+    pub language_version: Option<String>,
+    /// Lay out the project in one directory per namespace segment.
+    pub nested_directories: bool,
+    /// Directories searched for referenced assemblies.
+    pub references: Vec<PathBuf>,
+}
+
+/// Backend running the `ilspycmd` dotnet tool.
+#[derive(Debug, Clone)]
+pub struct IlSpy {
+    options: IlSpyOptions,
+    version: String,
+}
+
+impl IlSpy {
+    /// Locate `ilspycmd` and record its version.
+    pub fn detect(options: IlSpyOptions) -> Result<Self> {
+        let res = Self::command(&options)
+            .arg("--version")
+            .output()
+            .context("Could not run `ilspycmd`, is the dotnet tool installed?")?;
+
+        if !res.status.success() {
+            Err(anyhow!(
+                "`ilspycmd --version` failed ({}): {}",
+                res.status,
+                String::from_utf8_lossy(&res.stderr).trim()
+            ))?;
+        }
+
+        // Prints e.g. `ilspycmd: 8.2.0.7535`
+        let stdout = String::from_utf8_lossy(&res.stdout);
+        let version = stdout
+            .lines()
+            .find_map(|x| x.strip_prefix("ilspycmd:"))
+            .unwrap_or(stdout.trim())
+            .trim()
+            .to_string();
+
+        Ok(IlSpy { options, version })
+    }
+
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn command(options: &IlSpyOptions) -> Command {
+        match &options.program {
+            Some(program) => Command::new(program),
+            None => Command::new("ilspycmd"),
+        }
+    }
+}
+
+impl Decompiler for IlSpy {
+    fn name(&self) -> String {
+        format!("ilspycmd {}", self.version)
+    }
+
+    fn decompile(&self, input: &Path, output: &Path) -> Result<PathBuf> {
+        fs::create_dir_all(output)?;
+
+        let mut cmd = Self::command(&self.options);
+        cmd.arg("-p").arg("-o").arg(output);
+        if let Some(version) = &self.options.language_version {
+            cmd.arg("-lv").arg(version);
+        }
+        if self.options.nested_directories {
+            cmd.arg("--nested-directories");
+        }
+        for reference in &self.options.references {
+            cmd.arg("-r").arg(reference);
+        }
+        cmd.arg(input);
+
+        let res = cmd.output().context("Could not run `ilspycmd`")?;
+        if !res.status.success() {
+            Err(anyhow!(
+                "ilspycmd failed on {:?} ({}): {}",
+                input,
+                res.status,
+                String::from_utf8_lossy(&res.stderr).trim()
+            ))?;
+        }
+
+        Ok(output.to_path_buf())
+    }
+}
+
+/// Backend for inputs that are already C# sources, a directory or a single file.
+#[derive(Debug, Clone, Default)]
+pub struct Passthrough;
+
+impl Decompiler for Passthrough {
+    fn name(&self) -> String {
+        "passthrough".to_string()
+    }
+
+    fn needs_work_dir(&self) -> bool {
+        false
+    }
+
+    fn decompile(&self, input: &Path, _output: &Path) -> Result<PathBuf> {
+        if !input.exists() {
+            Err(anyhow!("Source path {:?} does not exist", input))?;
+        }
+        Ok(input.to_path_buf())
+    }
+}
+
+/// Backend writing canned sources, so the pipeline can run without any
+/// dotnet tooling installed.
+#[cfg(test)]
+#[derive(Debug, Clone, Default)]
+pub struct Fixture {
+    files: Vec<(PathBuf, String)>,
+}
+
+#[cfg(test)]
+impl Fixture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_file(mut self, path: impl Into<PathBuf>, source: impl Into<String>) -> Self {
+        self.files.push((path.into(), source.into()));
+        self
+    }
+}
+
+#[cfg(test)]
+impl Decompiler for Fixture {
+    fn name(&self) -> String {
+        format!("fixture ({} files)", self.files.len())
+    }
+
+    fn decompile(&self, _input: &Path, output: &Path) -> Result<PathBuf> {
+        for (path, source) in &self.files {
+            let path = output.join(path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, source)
+                .with_context(|| format!("Could not write fixture {:?}", path))?;
+        }
+        Ok(output.to_path_buf())
+    }
+}
+
+/// Pick the backend for `input` based on whether it is an assembly or sources.
+pub fn for_input(input: &Path, options: &IlSpyOptions) -> Result<Box<dyn Decompiler>> {
+    if !input.exists() {
+        Err(anyhow!("Input {:?} does not exist", input))?;
+    }
+
+    if input.is_dir() {
+        return Ok(Box::new(Passthrough));
+    }
+
+    match input.extension().and_then(|x| x.to_str()) {
+        Some("cs") => Ok(Box::new(Passthrough)),
+        Some("dll") | Some("exe") => Ok(Box::new(IlSpy::detect(options.clone())?)),
+        _ => Err(anyhow!(
+            "Unsupported input {:?}, expected a .dll, a .cs file or a directory",
+            input
+        )),
+    }
+}
@@ -0,0 +1,302 @@
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{Value, json};
+
+use crate::types::Span;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Severity {
+    #[default]
+    Error,
+    /// Reported, but the file was still extracted.
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A problem with a specific node of a source file.
+///
+/// Boxed so that the `Result`s threaded through every extractor stay small.
+#[derive(Debug, Clone)]
+pub struct Diagnostic(Box<Inner>);
+
+#[derive(Debug, Clone)]
+struct Inner {
+    path: PathBuf,
+    severity: Severity,
+    span: Span,
+    /// Grammar kind of the offending node, e.g. `field_declaration`.
+    kind: String,
+    message: String,
+    hint: Option<String>,
+    /// Line of source the span starts on, without the line break.
+    source_line: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(span: Span, kind: impl Into<String>, message: impl Into<String>) -> Self {
+        Diagnostic(Box::new(Inner {
+            path: PathBuf::new(),
+            severity: Severity::Error,
+            span,
+            kind: kind.into(),
+            message: message.into(),
+            hint: None,
+            source_line: None,
+        }))
+    }
+
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.0.severity = severity;
+        self
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.0.hint = Some(hint.into());
+        self
+    }
+
+    /// Keep the offending line so the diagnostic can still be rendered once
+    /// the source text is gone.
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.0.source_line = source
+            .lines()
+            .nth(self.0.span.line().saturating_sub(1))
+            .map(|x| x.trim_end_matches('\r').to_string());
+        self
+    }
+
+    /// The extractors only see the source text, the path is filled in once
+    /// the error reaches `parse_file`.
+    pub fn with_path(mut self, path: &Path) -> Self {
+        self.0.path = path.to_path_buf();
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0.path
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.0.severity
+    }
+
+    pub fn span(&self) -> Span {
+        self.0.span
+    }
+
+    pub fn kind(&self) -> &str {
+        &self.0.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.0.message
+    }
+
+    pub fn hint(&self) -> Option<&str> {
+        self.0.hint.as_deref()
+    }
+
+    /// Multi-line form with the source line and the span underlined, e.g.
+    ///
+    /// ```text
+    /// error: Unsupported type
+    ///   --> Player.cs:3:5
+    ///    |
+    ///  3 |     var x;
+    ///    |     ^^^ implicit_type
+    ///    = hint: `decode_type` has no case for `implicit_type` nodes
+    /// ```
+    pub fn render(&self) -> String {
+        let line = self.0.span.line().to_string();
+        let gutter = " ".repeat(line.len());
+        let mut res = format!(
+            "{}: {}\n{}--> {}:{}:{}",
+            self.0.severity,
+            self.0.message,
+            gutter,
+            self.0.path.display(),
+            self.0.span.line(),
+            self.0.span.column()
+        );
+        match &self.0.source_line {
+            Some(source) => {
+                let (padding, width) = self.underline(source);
+                res += &format!("\n{} |", gutter);
+                res += &format!("\n{} | {}", line, source);
+                res += &format!(
+                    "\n{} | {}{} {}",
+                    gutter,
+                    padding,
+                    "^".repeat(width),
+                    self.0.kind
+                );
+            }
+            None => res += &format!("\n{} = in: {}", gutter, self.0.kind),
+        }
+        if let Some(hint) = &self.0.hint {
+            res += &format!("\n{} = hint: {}", gutter, hint);
+        }
+        res
+    }
+
+    /// Whitespace up to the start of the span, tabs kept so the carets line
+    /// up, and the number of characters to underline on the first line.
+    fn underline(&self, source: &str) -> (String, usize) {
+        let start = (self.0.span.column() - 1).min(source.len());
+        let end = (start + self.0.span.end() - self.0.span.start()).min(source.len());
+        let padding = source
+            .get(..start)
+            .unwrap_or_default()
+            .chars()
+            .map(|x| if x == '\t' { '\t' } else { ' ' })
+            .collect();
+        let width = source.get(start..end).map_or(0, |x| x.chars().count());
+        (padding, width.max(1))
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "path": self.0.path.display().to_string(),
+            "severity": self.0.severity.to_string(),
+            "start": self.0.span.start(),
+            "end": self.0.span.end(),
+            "line": self.0.span.line(),
+            "column": self.0.span.column(),
+            "end_line": self.0.span.end_line(),
+            "end_column": self.0.span.end_column(),
+            "kind": self.0.kind,
+            "message": self.0.message,
+            "hint": self.0.hint,
+            "source": self.0.source_line,
+        })
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {} (in {})",
+            self.0.path.display(),
+            self.0.span.line(),
+            self.0.span.column(),
+            self.0.message,
+            self.0.kind
+        )
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// Why a source file could not be turned into a `CompilationUnit`.
+#[derive(Debug)]
+pub enum ParseError {
+    Io(PathBuf, io::Error),
+    /// Extraction failed, along with the syntax errors tree-sitter recovered
+    /// from beforehand.
+    Invalid(Diagnostic, Vec<Diagnostic>),
+    /// tree-sitter had to recover from these, never empty.
+    Syntax(Vec<Diagnostic>),
+}
+
+impl ParseError {
+    pub fn path(&self) -> &Path {
+        match self {
+            ParseError::Io(path, _) => path,
+            ParseError::Invalid(x, _) => x.path(),
+            ParseError::Syntax(x) => x[0].path(),
+        }
+    }
+
+    /// Grammar kind of the offending node, `io` if the file could not be read.
+    pub fn kind(&self) -> &str {
+        match self {
+            ParseError::Io(..) => "io",
+            ParseError::Invalid(x, _) => x.kind(),
+            ParseError::Syntax(x) => x[0].kind(),
+        }
+    }
+
+    pub fn render(&self) -> String {
+        match self {
+            ParseError::Io(..) => format!("error: {}", self),
+            ParseError::Invalid(x, syntax_errors) => std::iter::once(x)
+                .chain(syntax_errors)
+                .map(|x| x.render())
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+            ParseError::Syntax(x) => x
+                .iter()
+                .map(|x| x.render())
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        match self {
+            ParseError::Io(path, e) => json!({
+                "path": path.display().to_string(),
+                "kind": self.kind(),
+                "message": e.to_string(),
+            }),
+            ParseError::Invalid(x, syntax_errors) => {
+                let mut res = x.to_json();
+                if !syntax_errors.is_empty() {
+                    res["syntax_errors"] =
+                        syntax_errors.iter().map(|x| x.to_json()).collect::<Value>();
+                }
+                res
+            }
+            ParseError::Syntax(x) => json!({
+                "path": self.path().display().to_string(),
+                "kind": self.kind(),
+                "message": self.to_string(),
+                "errors": x.iter().map(|x| x.to_json()).collect::<Vec<_>>(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io(path, e) => write!(f, "Could not read {:?}: {}", path, e),
+            ParseError::Invalid(x, syntax_errors) if !syntax_errors.is_empty() => {
+                write!(f, "{} after {} syntax errors", x, syntax_errors.len())
+            }
+            ParseError::Invalid(x, _) => x.fmt(f),
+            ParseError::Syntax(x) if x.len() > 1 => {
+                write!(f, "{} and {} more syntax errors", x[0], x.len() - 1)
+            }
+            ParseError::Syntax(x) => x[0].fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Io(_, e) => Some(e),
+            // Displayed as is, there is nothing underneath.
+            ParseError::Invalid(..) | ParseError::Syntax(_) => None,
+        }
+    }
+}
+
+impl From<Diagnostic> for ParseError {
+    fn from(x: Diagnostic) -> Self {
+        ParseError::Invalid(x, Vec::new())
+    }
+}
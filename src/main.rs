@@ -1,79 +1,227 @@
 #![allow(unused)]
 
-use std::{collections::HashMap, env, fs, path::Path, process::Command};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{Context, Result, anyhow};
-// use proc_macro2::{Ident, TokenStream};
-// use quote::{format_ident, quote};
-use rand::Rng;
-use rand::distr::Alphanumeric;
-use tree_sitter::{Language, Node, Parser, Query, QueryCursor, QueryMatch, StreamingIterator};
-
+use clap::Parser as _;
+use tree_sitter::{Language, Parser};
+
+mod cli;
+mod codegen;
+mod decompiler;
+mod diagnostic;
+mod parser;
+mod queries;
+mod report;
+mod resolve;
+mod sources;
 mod types;
+mod workdir;
 
-use types::{Chunk, Class, Enum, Interface, Method, Modifier, Primitive, Struct, Type, Variable};
+use cli::{ChunkKind, Cli, Command, Filter, InputArgs, MessageFormat};
+use decompiler::{Decompiler, IlSpy};
+use queries::Queries;
+use report::Report;
+use resolve::{Origin, Resolver};
+use types::{Chunk, CompilationUnit};
+use workdir::WorkDir;
 
 fn main() -> Result<()> {
-    let args = env::args();
-    if args.len() != 2 {
-        println!("Usage: tsdsp <dll>");
-        println!("`ilspycmd` dotnet tool required.");
-        return Ok(());
+    let cli = Cli::parse();
+
+    let report = match &cli.command {
+        Command::Extract {
+            dll,
+            output,
+            decompile,
+        } => {
+            let backend = IlSpy::detect(decompile.options())?;
+            match output {
+                Some(output) => {
+                    extract(&backend, dll, output, cli.verbose)?;
+                }
+                None => {
+                    // The extracted sources are the whole point, never clean them up.
+                    let work_dir = WorkDir::open(cli.workdir.as_deref(), true)?;
+                    extract(&backend, dll, work_dir.path(), cli.verbose)?;
+                    println!("{}", work_dir.path().display());
+                }
+            }
+            None
+        }
+        Command::Parse(input) => {
+            let (units, report) = load(input, &cli)?;
+            for chunk in input.filter.select(chunks(&units)) {
+                println!("{:?} {}", ChunkKind::from(chunk), chunk.full_name());
+            }
+            Some(report)
+        }
+        Command::Stats(input) => {
+            let (units, report) = load(input, &cli)?;
+            print_stats(&input.filter.select(chunks(&units)));
+            print_origins(&units, &input.filter);
+            Some(report)
+        }
+        Command::GenRust { input, output } => {
+            let (units, report) = load(input, &cli)?;
+            let code = codegen::generate(&input.filter.select(chunks(&units))).to_string();
+            write_output(output.as_deref(), &code)?;
+            Some(report)
+        }
+        Command::DumpIr { input, output } => {
+            let (units, report) = load(input, &cli)?;
+            // Keep whole files so nested types and usings are shown in context.
+            let units = units
+                .iter()
+                .filter(|x| {
+                    x.chunks()
+                        .iter()
+                        .flat_map(|y| y.flatten())
+                        .any(|y| input.filter.matches(y))
+                })
+                .collect::<Vec<_>>();
+            write_output(output.as_deref(), &format!("{:#?}", units))?;
+            Some(report)
+        }
+    };
+
+    // With `--keep-going` the output covers the files that did parse, but the
+    // run still has to fail so scripts notice the others.
+    if let Some(report) = report
+        && report.failed() > 0
+    {
+        return Err(anyhow!(
+            "{} of {} files could not be parsed",
+            report.failed(),
+            report.parsed() + report.skipped() + report.failed()
+        ));
     }
-    let args = args.collect::<Vec<String>>();
-    let dll_name = args[1].as_str();
-
-    // let tmp_dir = env::temp_dir();
-    // let id = rand::rng()
-    //     .sample_iter(Alphanumeric)
-    //     .map(|x| x as char)
-    //     .take(16)
-    //     .collect::<String>();
-    // let work_dir = tmp_dir.join("tsdsp").join(id);
-    // fs::create_dir_all(&work_dir)?;
-    let work_dir = Path::new(
-        "/private/var/folders/_q/yycj0b893pzccg6kxlnf3m640000gn/T/tsdsp/Rw6ttZ8oTMi0jw5w",
-    );
-    let work_dir = work_dir.canonicalize()?;
+    Ok(())
+}
 
-    Command::new("ilspycmd")
-        .output()
-        .context("Could not run `ilspycmd`")?;
+fn extract(backend: &dyn Decompiler, input: &Path, output: &Path, verbose: u8) -> Result<PathBuf> {
+    if verbose > 0 {
+        eprintln!(
+            "Extracting {:?} to {:?} with {}",
+            input,
+            output,
+            backend.name()
+        );
+    }
+    backend.decompile(input, output)
+}
 
-    println!("Extracting {} to {:?}", dll_name, work_dir);
+/// The parsed files along with the report on those that were skipped or failed.
+fn load(input: &InputArgs, cli: &Cli) -> Result<(Vec<CompilationUnit>, Report)> {
+    let path = &input.input;
+    let backend = decompiler::for_input(path, &input.decompile.options())?;
+    if !backend.needs_work_dir() {
+        let root = backend.decompile(path, path)?;
+        return parse_sources(input, &root, cli.verbose);
+    }
 
-    // let res = Command::new("ilspycmd")
-    //     .arg("-p")
-    //     .arg("-o")
-    //     .arg(&work_dir)
-    //     .arg(dll_name)
-    //     .output()
-    //     .context("Could not run `ilspycmd`")?;
+    let work_dir = WorkDir::open(cli.workdir.as_deref(), cli.keep)?;
+    if cli.verbose > 0 {
+        eprintln!("Using work directory {:?}", work_dir.path());
+    }
 
-    // if !res.status.success() {
-    //     Err(anyhow!("ilspycmd failed"))?;
-    // }
+    let root = if work_dir.is_populated() {
+        if cli.verbose > 0 {
+            eprintln!("Reusing sources in {:?}", work_dir.path());
+        }
+        work_dir.path().to_path_buf()
+    } else {
+        extract(backend.as_ref(), path, work_dir.path(), cli.verbose)?
+    };
+    parse_sources(input, &root, cli.verbose)
+}
+
+fn parse_sources(
+    input: &InputArgs,
+    root: &Path,
+    verbose: u8,
+) -> Result<(Vec<CompilationUnit>, Report)> {
+    let files = sources::discover(root)?;
+    if verbose > 0 {
+        eprintln!("Found {} source files under {:?}", files.len(), root);
+    }
 
     let mut parser = Parser::new();
     let lang: Language = tree_sitter_c_sharp::LANGUAGE.into();
     parser
         .set_language(&lang)
         .expect("Error loading C# grammar");
+    let queries = Queries::new(&lang);
+
+    let keep_going = input.keep_going || input.report.is_some();
+    let mut report = Report::new();
+    let mut units = Vec::new();
+    for (i, file) in files.iter().enumerate() {
+        if verbose > 1 {
+            eprintln!("Parsing {:?}", file.relative);
+        }
+        match parser::parse_file(&mut parser, &queries, file, input.on_syntax_error) {
+            Ok(parsed) => {
+                for x in &parsed.syntax_errors {
+                    match input.message_format {
+                        MessageFormat::Human => eprintln!("{}\n", x.render()),
+                        MessageFormat::Json => eprintln!("{}", x.to_json()),
+                    }
+                }
+                report.add_syntax_errors(parsed.syntax_errors);
+                match parsed.unit {
+                    Some(unit) => {
+                        report.add_parsed();
+                        units.push(unit);
+                    }
+                    None => report.add_skipped(),
+                }
+            }
+            Err(e) => {
+                match input.message_format {
+                    MessageFormat::Human => eprintln!("{}\n", e.render()),
+                    MessageFormat::Json => eprintln!("{}", e.to_json()),
+                }
+                if !keep_going {
+                    return Err(anyhow!("Could not parse {:?}", file.relative));
+                }
+                report.add_failure(e);
+            }
+        }
 
-    let mut chunks = Vec::new();
-    let mut dir = fs::read_dir(&work_dir)?;
-    for (i, file) in dir.enumerate() {
-        let path = file?.path();
-        if !path.is_file() || path.extension().unwrap() != "cs" {
-            continue;
+        if verbose > 0 && i % 50 == 0 {
+            eprintln!("Processed {} files", i);
         }
-        chunks.push(parse_file(&mut parser, &lang, path)?);
+    }
 
-        if i % 50 == 0 {
-            println!("Processed {} files", i);
+    if keep_going {
+        match input.message_format {
+            MessageFormat::Human => eprintln!("{}", report),
+            MessageFormat::Json => eprintln!("{}", report.to_json()),
         }
     }
+    if let Some(path) = &input.report {
+        let json = serde_json::to_string_pretty(&report.to_json())?;
+        fs::write(path, json).with_context(|| format!("Could not write report to {:?}", path))?;
+    }
+
+    let resolver = Resolver::new(&units);
+    for unit in &mut units {
+        resolver.resolve(unit);
+    }
 
+    Ok((units, report))
+}
+
+fn chunks(units: &[CompilationUnit]) -> impl Iterator<Item = &Chunk> {
+    units.iter().flat_map(|x| x.chunks())
+}
+
+fn print_stats(chunks: &[&Chunk]) {
     let counts = chunks.iter().fold(
         (0, 0, 0, 0, 0),
         |(classes, enums, structs, interfaces, delegates), chunk| match chunk {
@@ -89,695 +237,134 @@ fn main() -> Result<()> {
         "Classes: {}, Enums: {}, Structs: {}, Interfaces: {}, Delegates: {}",
         counts.0, counts.1, counts.2, counts.3, counts.4
     );
-
-    Ok(())
-}
-
-fn parse_file(parser: &mut Parser, lang: &Language, path: std::path::PathBuf) -> Result<Chunk> {
-    let source = fs::read_to_string(path.clone())?;
-    let tree = parser
-        .parse(source.clone(), None)
-        .expect("Could not parse C# file");
-    let root = tree.root_node();
-
-    let query_type = Query::new(
-        lang,
-        r#"
-        [
-            (class_declaration)
-            (enum_declaration)
-            (struct_declaration)
-            (interface_declaration)
-            (delegate_declaration)
-        ] @type
-        "#,
-    )
-    .expect("Could not create query");
-
-    let mut cursor = QueryCursor::new();
-    let chunk_type = cursor
-        .matches(&query_type, root, source.as_bytes())
-        .map_deref(|x| x.captures)
-        .next()
-        .expect(&format!("No chunk found in {:?}", path.clone()))
-        .iter()
-        .next()
-        .expect("Empty capture")
-        .node
-        .kind();
-
-    match chunk_type {
-        "class_declaration" => extract_class(lang, root, &source),
-        "enum_declaration" => extract_enum(lang, root, &source),
-        "struct_declaration" => extract_struct(lang, root, &source),
-        "interface_declaration" => extract_interface(lang, root, &source),
-        "delegate_declaration" => extract_delegate(lang, root, &source),
-        _ => Err(anyhow!("Unknown chunk type")),
-    }
 }
 
-fn extract_delegate(lang: &Language, node: Node, source: &str) -> Result<Chunk> {
-    let delegate_query = Query::new(
-        lang,
-        r#"
-        (compilation_unit
-            (using_directive
-                [
-                    (identifier)
-                    (qualified_name)
-                ] @directive
-            )*
-            (delegate_declaration
-                (modifier)* @modifier
-                type: (_) @type
-                name: (identifier) @name
-                parameters: (parameter_list
-                    (parameter)* @parameter
-                )
-                body: (block)? @body
-            )
-        )
-        "#,
-    )
-    .expect("Failed to create query");
-
-    let captures = capture(&delegate_query, node, source);
-
-    let name = captures
-        .get("name")
-        .expect("Invalid field declaration")
-        .first()
-        .expect("Empty field name")
-        .utf8_text(source.as_bytes())
-        .expect("Error decoding text")
-        .to_string();
-
-    let mods = captures
-        .get("modifier")
-        .expect("Invalid field declaration")
-        .iter()
-        .map(|x| Modifier::from(x.utf8_text(source.as_bytes()).expect("Error decoding text")))
+/// Where the types referenced by the selected chunks are declared.
+fn print_origins(units: &[CompilationUnit], filter: &Filter) {
+    let resolver = Resolver::new(units);
+    let origins = chunks(units)
+        .flat_map(|x| resolver.origins(x, &|y| filter.matches(y)))
         .collect::<Vec<_>>();
+    let count = |origin| origins.iter().filter(|x| **x == origin).count();
 
-    let ty = decode_type(
-        captures
-            .get("type")
-            .expect("Invalid field declaration")
-            .first()
-            .expect("Empty type"),
-        source,
+    println!(
+        "Type references: Game: {}, Framework: {}, Unknown: {}",
+        count(Origin::Game),
+        count(Origin::Framework),
+        count(Origin::Unknown)
     );
-
-    let params = captures
-        .get("parameter")
-        .expect("Invalid field declaration")
-        .iter()
-        .map(|x| {
-            (
-                x.child_by_field_name("name")
-                    .expect("Invalid field name")
-                    .utf8_text(source.as_bytes())
-                    .expect("Error decoding text")
-                    .to_string(),
-                decode_type(
-                    &x.child_by_field_name("type").expect("Invalid field type"),
-                    source,
-                ),
-            )
-        })
-        .collect::<HashMap<_, _>>();
-
-    let body = captures
-        .get("body")
-        .expect("Invalid field declaration")
-        .first()
-        .map(|x| {
-            x.utf8_text(source.as_bytes())
-                .expect("Error decoding text")
-                .to_string()
-        })
-        .unwrap_or(String::new());
-
-    Ok(Chunk::Delegate(Method::new(name, mods, ty, params, body)))
-}
-
-fn extract_interface(lang: &Language, node: Node, source: &str) -> Result<Chunk> {
-    let interface_query = Query::new(
-        lang,
-        r#"
-        (compilation_unit
-            (using_directive
-                [
-                    (identifier)
-                    (qualified_name)
-                ] @directive
-            )*
-            (interface_declaration
-                (modifier)* @modifier
-                (identifier) @name
-                (base_list
-                    (identifier) @base
-                )?
-                (declaration_list
-                    [
-                        (property_declaration)* @property
-                        (method_declaration)* @method
-                    ]*
-                )?
-            )
-        )
-        "#,
-    )
-    .expect("Error creating query");
-
-    let captures = capture(&interface_query, node, source);
-
-    let name = captures
-        .get("name")
-        .expect("Invalid struct declaration")
-        .first()
-        .expect("Missing struct name")
-        .utf8_text(source.as_bytes())
-        .expect("Error decoding text")
-        .to_string();
-
-    let mods = captures
-        .get("modifier")
-        .expect("Invalid struct declaration")
-        .iter()
-        .map(|m| Modifier::from(m.utf8_text(source.as_bytes()).expect("Error decoding text")))
-        .collect::<Vec<_>>();
-
-    let base = captures
-        .get("base")
-        .expect("Invalid struct declaration")
-        .iter()
-        .map(
-            |x| match x.utf8_text(source.as_bytes()).expect("Error decoding text") {
-                "Object" => Type::Primitive(Primitive::Object),
-                s => Type::Object(s.to_string(), None),
-            },
-        )
-        .collect::<Vec<_>>();
-
-    let mut res = Interface::new(name, mods, base);
-
-    captures
-        .get("method")
-        .expect("Invalid class declaration")
-        .iter()
-        .cloned()
-        .map(|x| extract_method(&lang, x, &source).expect("Error decoding method"))
-        .for_each(|f| res.add_method(f));
-
-    Ok(Chunk::Interface(res))
-}
-
-fn extract_struct(lang: &Language, node: Node, source: &str) -> Result<Chunk> {
-    let struct_query = Query::new(
-        lang,
-        r#"
-        (compilation_unit
-            (using_directive
-                [
-                    (identifier)
-                    (qualified_name)
-                ] @directive
-            )*
-            (struct_declaration
-                (modifier)* @modifier
-                (identifier) @name
-                (base_list
-                    (identifier) @base
-                )?
-                (declaration_list
-                    [
-                        (field_declaration)* @field
-                        (method_declaration)* @method
-                    ]*
-                )?
-            )
-        )
-        "#,
-    )
-    .expect("Error creating query");
-
-    let captures = capture(&struct_query, node, source);
-
-    let name = captures
-        .get("name")
-        .expect("Invalid struct declaration")
-        .first()
-        .expect("Missing struct name")
-        .utf8_text(source.as_bytes())
-        .expect("Error decoding text")
-        .to_string();
-
-    let mods = captures
-        .get("modifier")
-        .expect("Invalid struct declaration")
-        .iter()
-        .map(|m| Modifier::from(m.utf8_text(source.as_bytes()).expect("Error decoding text")))
-        .collect::<Vec<_>>();
-
-    let base = captures
-        .get("base")
-        .expect("Invalid struct declaration")
-        .iter()
-        .map(
-            |x| match x.utf8_text(source.as_bytes()).expect("Error decoding text") {
-                "Object" => Type::Primitive(Primitive::Object),
-                s => Type::Object(s.to_string(), None),
-            },
-        )
-        .collect::<Vec<_>>();
-
-    let mut res = Struct::new(name, mods, base);
-
-    captures
-        .get("field")
-        .expect("Invalid class declaration")
-        .iter()
-        .cloned()
-        .map(|x| extract_field(&lang, x, &source).expect("Error decoding field"))
-        .for_each(|f| res.add_variable(f));
-
-    captures
-        .get("method")
-        .expect("Invalid class declaration")
-        .iter()
-        .cloned()
-        .map(|x| extract_method(&lang, x, &source).expect("Error decoding method"))
-        .for_each(|f| res.add_method(f));
-
-    Ok(Chunk::Struct(res))
 }
 
-fn extract_enum(lang: &Language, node: Node, source: &str) -> Result<Chunk> {
-    let enum_query = Query::new(
-        lang,
-        r#"
-        (compilation_unit
-            (using_directive
-                [
-                    (identifier)
-                    (qualified_name)
-                ] @directive
-            )*
-            (enum_declaration
-            	  (modifier)* @modifier
-                (identifier) @name
-                (base_list
-                    (identifier) @base
-                )?
-                (enum_member_declaration_list
-                	  (enum_member_declaration)* @member
-                )
-            )
-        )
-        "#,
-    )
-    .expect("Failed to create query");
-
-    let captures = capture(&enum_query, node, &source);
-
-    let name = captures
-        .get("name")
-        .expect("Invalid class declaration")
-        .first()
-        .expect("No class name")
-        .utf8_text(source.as_bytes())
-        .expect("Error decoding text")
-        .to_string();
-
-    let mods = captures
-        .get("modifier")
-        .expect("Invalid class declaration")
-        .iter()
-        .map(|x| Modifier::from(x.utf8_text(source.as_bytes()).expect("Error decoding text")))
-        .collect::<Vec<_>>();
-
-    let base = captures
-        .get("base")
-        .expect("Invalid class declaration")
-        .iter()
-        .map(
-            |x| match x.utf8_text(source.as_bytes()).expect("Error decoding text") {
-                "Object" => Type::Primitive(Primitive::Object),
-                s => Type::Object(s.to_string(), None),
-            },
-        )
-        .collect::<Vec<_>>();
-
-    let mut res = Enum::new(name, mods, base);
-
-    captures
-        .get("member")
-        .expect("Invalid enum declaration")
-        .iter()
-        .cloned()
-        .map(|x| {
-            (
-                x.child_by_field_name("name")
-                    .unwrap()
-                    .utf8_text(source.as_bytes())
-                    .expect("Error decoding text")
-                    .to_string(),
-                x.child_by_field_name("value").map(|y| {
-                    y.utf8_text(source.as_bytes())
-                        .expect("Error decoding text")
-                        .parse::<i32>()
-                        .expect(&format!("Enum not as i32: {}", source))
-                }),
-            )
-        })
-        .for_each(|(k, v)| res.add_value(k, v));
-
-    Ok(Chunk::Enum(res))
-}
-
-fn extract_class(lang: &Language, node: Node, source: &str) -> Result<Chunk> {
-    let class_query = Query::new(
-        lang,
-        r#"
-        (compilation_unit
-            (using_directive
-                [
-                    (identifier)
-                    (qualified_name)
-                ] @directive
-            )*
-            (class_declaration
-                (modifier)* @modifier
-                name: (identifier) @name
-                (base_list
-                    (identifier) @base
-                )?
-                (declaration_list
-                    [
-                        (field_declaration)* @field
-                        (property_declaration)* @property
-                        (constructor_declaration)* @constructor
-                        (destructor_declaration)* @destructor
-                        (method_declaration)* @method
-                    ]*
-                )?
-            )
-        )
-        "#,
-    )
-    .expect("Invalid query");
-
-    let captures = capture(&class_query, node, &source);
-
-    let name = captures
-        .get("name")
-        .expect("Invalid class declaration")
-        .first()
-        .expect("No class name")
-        .utf8_text(source.as_bytes())
-        .expect("Error decoding text")
-        .to_string();
-
-    let mods = captures
-        .get("modifier")
-        .expect("Invalid class declaration")
-        .iter()
-        .map(|x| Modifier::from(x.utf8_text(source.as_bytes()).expect("Error decoding text")))
-        .collect::<Vec<_>>();
-
-    let base = captures
-        .get("base")
-        .expect("Invalid class declaration")
-        .iter()
-        .map(
-            |x| match x.utf8_text(source.as_bytes()).expect("Error decoding text") {
-                "Object" => Type::Primitive(Primitive::Object),
-                s => Type::Object(s.to_string(), None),
-            },
-        )
-        .collect::<Vec<_>>();
-
-    let mut cls = Class::new(name, mods, base);
-
-    captures
-        .get("field")
-        .expect("Invalid class declaration")
-        .iter()
-        .cloned()
-        .map(|x| extract_field(&lang, x, &source).expect("Error decoding field"))
-        .for_each(|f| cls.add_variable(f));
-
-    captures
-        .get("method")
-        .expect("Invalid class declaration")
-        .iter()
-        .cloned()
-        .map(|x| extract_method(&lang, x, &source).expect("Error decoding method"))
-        .for_each(|f| cls.add_method(f));
-
-    Ok(Chunk::Class(cls))
+fn write_output(path: Option<&Path>, content: &str) -> Result<()> {
+    match path {
+        Some(path) => fs::write(path, content)
+            .with_context(|| format!("Could not write output to {:?}", path)),
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            writeln!(stdout, "{}", content)?;
+            Ok(())
+        }
+    }
 }
 
-fn capture<'a>(query: &Query, node: Node<'a>, source: &str) -> HashMap<String, Vec<Node<'a>>> {
-    let mut cursor = QueryCursor::new();
-    let res = cursor
-        .matches(query, node, source.as_bytes())
-        .map_deref(|y| y.captures)
-        .next()
-        .expect(&format!(
-            "Invalid query for node: {}",
-            node.utf8_text(source.as_bytes())
-                .expect("Error decoding text")
-        ));
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use decompiler::Fixture;
 
-    query
-        .capture_names()
-        .iter()
-        .filter_map(|&x| {
-            if let Some(index) = query.capture_index_for_name(x) {
-                Some((x, index))
-            } else {
-                None
-            }
-        })
-        .map(|(x, i)| {
-            (
-                x.to_string(),
-                res.iter()
-                    .filter_map(|y| if y.index == i { Some(y.node) } else { None })
-                    .collect::<Vec<_>>(),
-            )
-        })
-        .collect()
-}
-
-fn extract_field(lang: &Language, node: Node, source: &str) -> Result<Variable> {
-    let field_query = Query::new(
-        lang,
-        r#"
-        (field_declaration
-            (modifier)* @modifier
-            (variable_declaration
-                type: (_) @type
-                (variable_declarator
-                    name: (identifier) @name
-                    [
-                        (integer_literal)
-                        (real_literal)
-                        (string_literal)
-                        (boolean_literal)
-                    ]? @value
-                )
-            )
-        )
-        "#,
-    )
-    .expect("Invalid query");
-
-    let captures = capture(&field_query, node, source);
-
-    let name = captures
-        .get("name")
-        .expect("Invalid field declaration")
-        .first()
-        .expect("Empty field name")
-        .utf8_text(source.as_bytes())
-        .expect("Error decoding text")
-        .to_string();
-
-    let mods = captures
-        .get("modifier")
-        .expect("Invalid field declaration")
-        .iter()
-        .map(|x| Modifier::from(x.utf8_text(source.as_bytes()).expect("Error decoding text")))
-        .collect::<Vec<_>>();
+    /// Run `fixture` through the same pipeline as the `parse` command.
+    pub(crate) fn parse(fixture: &Fixture) -> Result<Vec<CompilationUnit>> {
+        run(fixture, &[]).map(|(units, _)| units)
+    }
 
-    let ty = decode_type(
-        captures
-            .get("type")
-            .expect("Invalid field declaration")
-            .first()
-            .expect("Empty type"),
-        source,
-    );
+    /// Same as `parse`, with extra command line arguments.
+    fn run(fixture: &Fixture, args: &[&str]) -> Result<(Vec<CompilationUnit>, Report)> {
+        let work_dir = WorkDir::create(false)?;
+        let root = fixture.decompile(work_dir.path(), work_dir.path())?;
+        let cli = Cli::try_parse_from(
+            ["tsdsp", "parse"]
+                .iter()
+                .chain(args)
+                .map(|x| x.as_ref())
+                .chain([root.as_os_str()]),
+        )?;
+        let Command::Parse(input) = &cli.command else {
+            unreachable!()
+        };
+        parse_sources(input, &root, 0)
+    }
 
-    let value = captures
-        .get("value")
-        .expect("Invalid field declaration")
-        .first()
-        .map(|x| {
-            x.utf8_text(source.as_bytes())
-                .expect("Error decoding text")
-                .to_string()
-        });
-
-    Ok(Variable::new(name, mods, ty, value))
-}
+    /// The chunk named `full_name`, nested ones included.
+    pub(crate) fn find<'a>(units: &'a [CompilationUnit], full_name: &str) -> &'a Chunk {
+        chunks(units)
+            .flat_map(|x| x.flatten())
+            .find(|x| x.full_name() == full_name)
+            .unwrap_or_else(|| panic!("No chunk named {}", full_name))
+    }
 
-fn decode_type(node: &Node, source: &str) -> Type {
-    match node.kind() {
-        "identifier" => Type::Object(
-            node.utf8_text(source.as_bytes())
-                .expect("Error decoding text")
-                .to_string(),
-            None,
-        ),
-        "predefined_type" => Type::Primitive(Primitive::from(
-            node.utf8_text(source.as_bytes())
-                .expect("Error decoding text"),
-        )),
-        "array_type" => {
-            let sub_type = decode_type(&node.child_by_field_name("type").unwrap(), source);
-            match sub_type {
-                Type::Array(ty, rank) => Type::Array(ty, rank + 1),
-                _ => Type::Array(Box::new(sub_type), 1),
-            }
-        }
-        "generic_name" => {
-            let children = node.children(&mut node.walk()).collect::<Vec<_>>();
-            let name = children
-                .iter()
-                .filter(|&x| x.grammar_name() == "identifier")
-                .next()
-                .expect("Invalid generic_name node")
-                .utf8_text(source.as_bytes())
-                .expect("Error decoding text")
-                .to_string();
-            let sub_type = decode_type(
-                &children
-                    .iter()
-                    .filter(|x| x.grammar_name() == "type_argument_list")
-                    .next()
-                    .expect("Invalid generic_name node")
-                    .child(1)
-                    .expect("Invalid type argument list"),
-                source,
-            );
-            Type::Object(name, Some(Box::new(sub_type)))
-        }
-        "qualified_name" => {
-            let name = node
-                .utf8_text(source.as_bytes())
-                .expect("Error decoding text")
-                .to_string();
-            Type::Object(name, None)
-        }
-        "ref_type" => {
-            let sub_type = decode_type(
-                &node.child_by_field_name("type").expect("Invalid ref type"),
-                source,
-            );
-            Type::Reference(Box::new(sub_type))
-        }
-        "alias_qualified_name" => {
-            let name = node
-                .utf8_text(source.as_bytes())
-                .expect("Error decoding text")
-                .to_string();
-            Type::Object(name, None)
-        }
-        _ => panic!(
-            "Invalid type: {}",
-            node.utf8_text(source.as_bytes())
-                .expect("Error decoding text")
-        ),
+    #[test]
+    fn parses_every_file() {
+        let fixture = Fixture::new()
+            .with_file("A.cs", "namespace Game { class A {} }")
+            .with_file("B/B.cs", "namespace Game.B { struct B {} enum E { X } }");
+        let units = parse(&fixture).unwrap();
+
+        assert_eq!(units.len(), 2);
+        assert!(matches!(find(&units, "Game.A"), Chunk::Class(_)));
+        assert!(matches!(find(&units, "Game.B.B"), Chunk::Struct(_)));
+        assert!(matches!(find(&units, "Game.B.E"), Chunk::Enum(_)));
     }
-}
 
-fn extract_method(lang: &Language, node: Node, source: &str) -> Result<Method> {
-    let method_query = Query::new(
-        lang,
-        r#"
-        (method_declaration
-            (modifier)* @modifier
-            returns: (_) @type
-            name: (identifier) @name
-            parameters: (parameter_list
-                (parameter)* @parameter
-            )
-            body: (block)? @body
-        )
-        "#,
-    )
-    .expect("Invalid query");
-
-    let captures = capture(&method_query, node, source);
-
-    let name = captures
-        .get("name")
-        .expect("Invalid field declaration")
-        .first()
-        .expect("Empty field name")
-        .utf8_text(source.as_bytes())
-        .expect("Error decoding text")
-        .to_string();
-
-    let mods = captures
-        .get("modifier")
-        .expect("Invalid field declaration")
-        .iter()
-        .map(|x| Modifier::from(x.utf8_text(source.as_bytes()).expect("Error decoding text")))
-        .collect::<Vec<_>>();
+    #[test]
+    fn stops_at_the_first_failure() {
+        let fixture = Fixture::new().with_file("A.cs", "namespace Game { record R(int X); }");
+        assert!(parse(&fixture).is_err());
+    }
 
-    let ty = decode_type(
-        captures
-            .get("type")
-            .expect("Invalid field declaration")
-            .first()
-            .expect("Empty type"),
-        source,
-    );
+    #[test]
+    fn keep_going_reports_every_failure() {
+        let work_dir = WorkDir::create(false).unwrap();
+        let path = work_dir.path().join("report.json");
+        let fixture = Fixture::new()
+            .with_file("A.cs", "class A {}")
+            .with_file("B.cs", "record B(int X);")
+            .with_file("C.cs", "class C { int a = 1 +; }")
+            .with_file("D.cs", "class D { event System.Action E; }");
+        let (units, report) = run(&fixture, &["--report", path.to_str().unwrap()]).unwrap();
+
+        assert_eq!(units.len(), 2);
+        assert_eq!(
+            (report.parsed(), report.skipped(), report.failed()),
+            (2, 0, 2)
+        );
+        assert_eq!(report.syntax_errors().len(), 1);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["failed"], 2);
+        let kinds = json["constructs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| x["kind"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, ["record_declaration", "event_field_declaration"]);
+        assert_eq!(json["constructs"][0]["failures"][0]["line"], 1);
+        assert_eq!(json["syntax_errors"][0]["kind"], "identifier");
+    }
 
-    let params = captures
-        .get("parameter")
-        .expect("Invalid field declaration")
-        .iter()
-        .map(|x| {
-            (
-                x.child_by_field_name("name")
-                    .expect("Invalid field name")
-                    .utf8_text(source.as_bytes())
-                    .expect("Error decoding text")
-                    .to_string(),
-                decode_type(
-                    &x.child_by_field_name("type").expect("Invalid field type"),
-                    source,
-                ),
-            )
-        })
-        .collect::<HashMap<_, _>>();
-
-    let body = captures
-        .get("body")
-        .expect("Invalid field declaration")
-        .first()
-        .map(|x| {
-            x.utf8_text(source.as_bytes())
-                .expect("Error decoding text")
-                .to_string()
-        })
-        .unwrap_or(String::new());
-
-    Ok(Method::new(name, mods, ty, params, body))
+    #[test]
+    fn syntax_errors_fail_the_file_on_request() {
+        let fixture = Fixture::new()
+            .with_file("A.cs", "class A {}")
+            .with_file("B.cs", "class B { int a = 1 +; }");
+        let (units, report) =
+            run(&fixture, &["--keep-going", "--on-syntax-error", "fail"]).unwrap();
+        assert_eq!(units.len(), 1);
+        assert_eq!(report.failed(), 1);
+
+        let (units, report) = run(&fixture, &["--on-syntax-error", "skip"]).unwrap();
+        assert_eq!(units.len(), 1);
+        assert_eq!((report.skipped(), report.failed()), (1, 0));
+    }
 }
@@ -0,0 +1,2118 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use clap::ValueEnum;
+use tree_sitter::{Node, Parser, Query, QueryCursor, StreamingIterator, Tree};
+
+use crate::diagnostic::{Diagnostic, ParseError, Severity};
+use crate::queries::Queries;
+use crate::sources::SourceFile;
+use crate::types::{
+    Accessor, AccessorKind, Attribute, Chunk, Class, CompilationUnit, Constraint, Constructor,
+    ConstructorInitializer, Enum, EnumMember, Expression, Finalizer, Interface, Literal, Method,
+    Modifier, Parameter, ParameterModifier, Primitive, Property, RealKind, Span, Struct,
+    TupleElement, Type, TypeParameter, Using, Variable, Variance,
+};
+
+type Result<T> = std::result::Result<T, Diagnostic>;
+
+const TYPE_DECLARATIONS: &[&str] = &[
+    "class_declaration",
+    "enum_declaration",
+    "struct_declaration",
+    "interface_declaration",
+    "delegate_declaration",
+    // Not extracted yet, listed so they are reported instead of dropped.
+    "record_declaration",
+];
+
+/// What to do with a file tree-sitter could only parse by recovering from
+/// syntax errors, whose captures around the error may be wrong.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OnSyntaxError {
+    /// Leave the file out
+    Skip,
+    /// Extract what can be extracted
+    #[default]
+    BestEffort,
+    /// Treat the file as failed
+    Fail,
+}
+
+/// A source file after parsing, `unit` is `None` if it was skipped.
+#[derive(Debug)]
+pub struct Parsed {
+    pub unit: Option<CompilationUnit>,
+    pub syntax_errors: Vec<Diagnostic>,
+}
+
+pub fn parse_file(
+    parser: &mut Parser,
+    queries: &Queries,
+    file: &SourceFile,
+    on_syntax_error: OnSyntaxError,
+) -> std::result::Result<Parsed, ParseError> {
+    let path = file.path.as_path();
+    let source = fs::read_to_string(path).map_err(|e| ParseError::Io(path.to_path_buf(), e))?;
+    let tree = parser.parse(&source, None).ok_or_else(|| {
+        Diagnostic::new(
+            Span::default(),
+            "compilation_unit",
+            "Could not parse C# file",
+        )
+        .with_path(path)
+    })?;
+
+    let severity = match on_syntax_error {
+        OnSyntaxError::Fail => Severity::Error,
+        _ => Severity::Warning,
+    };
+    let syntax_errors = syntax_errors(tree.root_node())
+        .into_iter()
+        .map(|x| {
+            x.with_path(path)
+                .with_source(&source)
+                .with_severity(severity)
+        })
+        .collect::<Vec<_>>();
+    if !syntax_errors.is_empty() {
+        match on_syntax_error {
+            OnSyntaxError::Skip => {
+                return Ok(Parsed {
+                    unit: None,
+                    syntax_errors,
+                });
+            }
+            OnSyntaxError::BestEffort => {}
+            OnSyntaxError::Fail => return Err(ParseError::Syntax(syntax_errors)),
+        }
+    }
+
+    let unit = extract_unit(queries, &tree, file, &source).map_err(|e| {
+        ParseError::Invalid(
+            e.with_path(path).with_source(&source),
+            syntax_errors.clone(),
+        )
+    })?;
+    Ok(Parsed {
+        unit: Some(unit),
+        syntax_errors,
+    })
+}
+
+/// Every ERROR and MISSING node, outermost only since the nodes inside an
+/// ERROR node are a guess anyway.
+fn syntax_errors(node: Node) -> Vec<Diagnostic> {
+    if node.is_error() {
+        let parent = node.parent().map_or("compilation_unit", |x| x.kind());
+        let error = error(&node, format!("Syntax error in {}", parent))
+            .with_hint("tree-sitter skipped this text, captures around it may be wrong");
+        return vec![error];
+    }
+    if !node.has_error() {
+        return Vec::new();
+    }
+    let res = node
+        .children(&mut node.walk())
+        .flat_map(syntax_errors)
+        .collect::<Vec<_>>();
+    // A missing token inside e.g. an `identifier` is hidden from `children`,
+    // the error then only shows on the node that wraps it.
+    if res.is_empty() {
+        let parent = node.parent().map_or("compilation_unit", |x| x.kind());
+        let error = error(&node, format!("Missing `{}` in {}", node.kind(), parent))
+            .with_hint("tree-sitter inserted this node to recover from a syntax error");
+        return vec![error];
+    }
+    res
+}
+
+fn extract_unit(
+    queries: &Queries,
+    tree: &Tree,
+    file: &SourceFile,
+    source: &str,
+) -> Result<CompilationUnit> {
+    let root = tree.root_node();
+
+    let mut cursor = QueryCursor::new();
+    let mut nodes = Vec::new();
+    let mut matches = cursor.matches(&queries.types, root, source.as_bytes());
+    while let Some(m) = matches.next() {
+        nodes.extend(m.captures.iter().map(|x| x.node));
+    }
+
+    // Nested declarations are extracted by their enclosing type.
+    let chunks = nodes
+        .into_iter()
+        .filter(|x| !is_nested(x))
+        .map(|node| {
+            let mut chunk = extract_chunk(queries, node, source, None)?;
+            // Declarations outside any namespace block are placed by the
+            // directory layout of the decompiled project instead.
+            let namespace = namespace_of(&node, source)?.or_else(|| file.namespace_hint());
+            chunk.set_namespace(namespace);
+            Ok(chunk)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let usings = extract_usings(queries, root, source)?;
+
+    Ok(CompilationUnit::new(file.path.clone(), usings, chunks))
+}
+
+/// Every using directive in the file, including those inside namespace
+/// blocks, along with the span of the innermost block it is declared in.
+fn extract_usings(
+    queries: &Queries,
+    node: Node,
+    source: &str,
+) -> Result<Vec<(Using, Option<Span>)>> {
+    let mut cursor = QueryCursor::new();
+    let mut nodes = Vec::new();
+    let mut matches = cursor.matches(&queries.usings, node, source.as_bytes());
+    while let Some(m) = matches.next() {
+        nodes.extend(m.captures.iter().map(|x| x.node));
+    }
+
+    nodes
+        .iter()
+        .map(|x| {
+            let mut block = x.parent();
+            while let Some(y) = block
+                && y.kind() != "namespace_declaration"
+            {
+                block = y.parent();
+            }
+            Ok((extract_using(x, source)?, block.map(|y| span(&y))))
+        })
+        .collect()
+}
+
+fn extract_using(node: &Node, source: &str) -> Result<Using> {
+    let alias = node.child_by_field_name("name");
+    let target = node
+        .named_children(&mut node.walk())
+        .find(|x| Some(*x) != alias)
+        .ok_or_else(|| error(node, "Missing using target"))?;
+
+    if let Some(alias) = alias {
+        let alias = text(&alias, source)?.to_string();
+        return Ok(Using::Alias(alias, decode_type(&target, source)?));
+    }
+
+    let target = text(&target, source)?.to_string();
+    if node
+        .children(&mut node.walk())
+        .any(|x| x.kind() == "static")
+    {
+        Ok(Using::Static(target))
+    } else {
+        Ok(Using::Namespace(target))
+    }
+}
+
+/// Fully qualified namespace enclosing `node`, across nested block namespaces
+/// and a file scoped namespace.
+fn namespace_of(node: &Node, source: &str) -> Result<Option<String>> {
+    let mut parts = Vec::new();
+    let mut current = *node;
+    while let Some(parent) = current.parent() {
+        match parent.kind() {
+            "namespace_declaration" => parts.extend(namespace_name(&parent, source)?),
+            "compilation_unit" => {
+                // A file scoped namespace is a sibling preceding the declarations it covers.
+                let file_scoped = parent
+                    .named_children(&mut parent.walk())
+                    .take_while(|x| x.start_byte() < current.start_byte())
+                    .filter(|x| x.kind() == "file_scoped_namespace_declaration")
+                    .last();
+                if let Some(x) = file_scoped {
+                    parts.extend(namespace_name(&x, source)?);
+                }
+            }
+            _ => {}
+        }
+        current = parent;
+    }
+
+    if parts.is_empty() {
+        Ok(None)
+    } else {
+        parts.reverse();
+        Ok(Some(parts.join(".")))
+    }
+}
+
+fn namespace_name(node: &Node, source: &str) -> Result<Option<String>> {
+    node.child_by_field_name("name")
+        .map(|x| Ok(text(&x, source)?.to_string()))
+        .transpose()
+}
+
+fn is_nested(node: &Node) -> bool {
+    let mut parent = node.parent();
+    while let Some(x) = parent {
+        if TYPE_DECLARATIONS.contains(&x.kind()) {
+            return true;
+        }
+        parent = x.parent();
+    }
+    false
+}
+
+/// Declarations in the body of a class, struct or interface, in source
+/// order and with comments left out.
+fn body_declarations<'a>(node: &Node<'a>) -> Vec<Node<'a>> {
+    match node.child_by_field_name("body") {
+        Some(body) => body
+            .named_children(&mut body.walk())
+            .filter(|x| x.kind() != "comment")
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+fn unsupported_member(node: &Node) -> Diagnostic {
+    error(node, "Unsupported member declaration").with_hint(format!(
+        "`{}` has no IR representation in this type yet",
+        node.kind()
+    ))
+}
+
+fn span(node: &Node) -> Span {
+    let start = node.start_position();
+    let end = node.end_position();
+    Span::new(
+        node.start_byte(),
+        node.end_byte(),
+        start.row + 1,
+        start.column + 1,
+        end.row + 1,
+        end.column + 1,
+    )
+}
+
+fn error(node: &Node, message: impl Into<String>) -> Diagnostic {
+    Diagnostic::new(span(node), node.kind(), message)
+}
+
+fn text<'a>(node: &Node, source: &'a str) -> Result<&'a str> {
+    node.utf8_text(source.as_bytes())
+        .map_err(|_| error(node, "Invalid UTF-8"))
+}
+
+/// Required child `name` of `node`.
+fn field<'a>(node: &Node<'a>, name: &str) -> Result<Node<'a>> {
+    node.child_by_field_name(name)
+        .ok_or_else(|| missing(node, name))
+}
+
+fn missing(node: &Node, name: &str) -> Diagnostic {
+    error(node, format!("Missing {}", name))
+        .with_hint("The node has no such child, the source may not be valid C#")
+}
+
+/// Node text converted to one of the keyword enums, e.g. a `Modifier`.
+fn keyword<'a, T>(node: &Node, source: &'a str) -> Result<T>
+where
+    T: TryFrom<&'a str, Error = String>,
+{
+    T::try_from(text(node, source)?)
+        .map_err(|e| error(node, e).with_hint("The keyword has no variant in the IR yet"))
+}
+
+fn keywords<'a, T>(nodes: &[Node], source: &'a str) -> Result<Vec<T>>
+where
+    T: TryFrom<&'a str, Error = String>,
+{
+    nodes.iter().map(|x| keyword(x, source)).collect()
+}
+
+fn qualify(parent: Option<&str>, name: String) -> String {
+    match parent {
+        Some(parent) => format!("{}.{}", parent, name),
+        None => name,
+    }
+}
+
+fn extract_chunk(
+    queries: &Queries,
+    node: Node,
+    source: &str,
+    parent: Option<&str>,
+) -> Result<Chunk> {
+    match node.kind() {
+        "class_declaration" => extract_class(queries, node, source, parent),
+        "enum_declaration" => extract_enum(queries, node, source, parent),
+        "struct_declaration" => extract_struct(queries, node, source, parent),
+        "interface_declaration" => extract_interface(queries, node, source, parent),
+        "delegate_declaration" => extract_delegate(queries, node, source, parent),
+        "record_declaration" => Err(error(&node, "Records are not supported")
+            .with_hint("Record classes and record structs have no IR representation yet")),
+        _ => Err(error(&node, "Unknown chunk type")),
+    }
+}
+
+fn extract_delegate(
+    queries: &Queries,
+    node: Node,
+    source: &str,
+    parent: Option<&str>,
+) -> Result<Chunk> {
+    let captures = capture(&queries.delegate, node, source)?;
+
+    let name = text(&captures.required("name")?, source)?.to_string();
+    let name = qualify(parent, name);
+
+    let mods = keywords(captures.all("modifier"), source)?;
+
+    let ty = decode_type(&captures.required("type")?, source)?;
+
+    let params = extract_parameters(&captures.required("parameters")?, source)?;
+
+    let body = captures
+        .optional("body")
+        .map(|x| Ok(text(&x, source)?.to_string()))
+        .transpose()?
+        .unwrap_or_default();
+
+    let mut res = Method::new(name, mods, ty, params, body);
+    res.set_type_parameters(extract_type_parameters(&node, source)?);
+    res.set_attributes(extract_attributes(&node, source)?);
+    res.set_span(span(&node));
+
+    Ok(Chunk::Delegate(res))
+}
+
+fn extract_interface(
+    queries: &Queries,
+    node: Node,
+    source: &str,
+    parent: Option<&str>,
+) -> Result<Chunk> {
+    let captures = capture(&queries.interface, node, source)?;
+
+    let name = text(&captures.required("name")?, source)?.to_string();
+    let name = qualify(parent, name);
+
+    let mods = keywords(captures.all("modifier"), source)?;
+
+    let base = captures
+        .all("base")
+        .iter()
+        .map(|x| decode_base(x, source))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut res = Interface::new(name.clone(), mods, base);
+    res.set_type_parameters(extract_type_parameters(&node, source)?);
+    res.set_attributes(extract_attributes(&node, source)?);
+    res.set_span(span(&node));
+
+    for x in body_declarations(&node) {
+        match x.kind() {
+            "property_declaration" => res.add_property(extract_property(queries, x, source)?),
+            "method_declaration" => res.add_method(extract_method(queries, x, source)?),
+            kind if TYPE_DECLARATIONS.contains(&kind) => {
+                res.add_nested(extract_chunk(queries, x, source, Some(&name))?)
+            }
+            _ => return Err(unsupported_member(&x)),
+        }
+    }
+
+    Ok(Chunk::Interface(res))
+}
+
+fn extract_struct(
+    queries: &Queries,
+    node: Node,
+    source: &str,
+    parent: Option<&str>,
+) -> Result<Chunk> {
+    let captures = capture(&queries.struct_, node, source)?;
+
+    let name = text(&captures.required("name")?, source)?.to_string();
+    let name = qualify(parent, name);
+
+    let mods = keywords(captures.all("modifier"), source)?;
+
+    let base = captures
+        .all("base")
+        .iter()
+        .map(|x| decode_base(x, source))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut res = Struct::new(name.clone(), mods, base);
+    res.set_type_parameters(extract_type_parameters(&node, source)?);
+    res.set_attributes(extract_attributes(&node, source)?);
+    res.set_span(span(&node));
+
+    for x in body_declarations(&node) {
+        match x.kind() {
+            "field_declaration" => res.add_variables(extract_field(queries, x, source)?),
+            "property_declaration" => res.add_property(extract_property(queries, x, source)?),
+            "constructor_declaration" => {
+                res.add_constructor(extract_constructor(queries, x, source)?)
+            }
+            "method_declaration" => res.add_method(extract_method(queries, x, source)?),
+            kind if TYPE_DECLARATIONS.contains(&kind) => {
+                res.add_nested(extract_chunk(queries, x, source, Some(&name))?)
+            }
+            _ => return Err(unsupported_member(&x)),
+        }
+    }
+
+    Ok(Chunk::Struct(res))
+}
+
+fn extract_enum(
+    queries: &Queries,
+    node: Node,
+    source: &str,
+    parent: Option<&str>,
+) -> Result<Chunk> {
+    let captures = capture(&queries.enum_, node, source)?;
+
+    let name = text(&captures.required("name")?, source)?.to_string();
+    let name = qualify(parent, name);
+
+    let mods = keywords(captures.all("modifier"), source)?;
+
+    let underlying = captures
+        .optional("base")
+        .map(|x| decode_underlying(&x, source))
+        .transpose()?
+        .unwrap_or(Primitive::Int);
+
+    let simple_name = name.rsplit('.').next().unwrap_or(&name).to_string();
+    let mut res = Enum::new(name, mods, underlying.clone());
+    res.set_attributes(extract_attributes(&node, source)?);
+    res.set_span(span(&node));
+
+    // Members without a value follow the previous one, starting at zero.
+    let members = captures.required("members")?;
+    let mut next = Some(0);
+    for x in members
+        .named_children(&mut members.walk())
+        .filter(|x| x.kind() == "enum_member_declaration")
+    {
+        let name = text(&field(&x, "name")?, source)?.to_string();
+        let value = match x.child_by_field_name("value") {
+            Some(y) => evaluate(
+                &decode_expression(&y, source)?,
+                &simple_name,
+                res.members(),
+                &underlying,
+            )
+            .map(|y| wrap(y, &underlying)),
+            None => next,
+        };
+        next = value.map(|y| y + 1);
+        res.add_member(EnumMember::new(name, value, span(&x)));
+    }
+
+    Ok(Chunk::Enum(res))
+}
+
+/// Underlying type of an enum, by keyword or by its `System` name.
+fn decode_underlying(node: &Node, source: &str) -> Result<Primitive> {
+    let text = text(node, source)?;
+    let res = match text.rsplit('.').next().unwrap_or(text) {
+        "Byte" => Primitive::Byte,
+        "SByte" => Primitive::Sbyte,
+        "Int16" => Primitive::Short,
+        "UInt16" => Primitive::Ushort,
+        "Int32" => Primitive::Int,
+        "UInt32" => Primitive::Uint,
+        "Int64" => Primitive::Long,
+        "UInt64" => Primitive::Ulong,
+        x => Primitive::try_from(x).map_err(|e| error(node, e))?,
+    };
+    Ok(res)
+}
+
+/// Value of an integral constant expression. Names are looked up among the
+/// members declared so far, `None` if anything else is referenced.
+///
+/// `underlying` decides the shift count mask: C# masks it to the width of
+/// the operand, which is at least an `int`.
+fn evaluate(
+    expression: &Expression,
+    enum_name: &str,
+    members: &[EnumMember],
+    underlying: &Primitive,
+) -> Option<i128> {
+    let eval = |x: &Expression| evaluate(x, enum_name, members, underlying);
+    let mask = match underlying {
+        Primitive::Long | Primitive::Ulong => 0x3f,
+        _ => 0x1f,
+    };
+    let member = |name: &str| members.iter().find(|x| x.name() == name)?.value();
+    match expression {
+        Expression::Literal(Literal::Integer(x)) => Some(*x),
+        Expression::Literal(Literal::Char(x)) => Some(*x as i128),
+        Expression::Name(x) => member(x),
+        Expression::MemberAccess(x, name) => match x.as_ref() {
+            Expression::Name(x) if x == enum_name => member(name),
+            _ => None,
+        },
+        Expression::Unary(op, x) => {
+            let x = eval(x)?;
+            match op.as_str() {
+                "-" => x.checked_neg(),
+                "+" => Some(x),
+                "~" => Some(!x),
+                _ => None,
+            }
+        }
+        Expression::Binary(x, op, y) => {
+            let (x, y) = (eval(x)?, eval(y)?);
+            match op.as_str() {
+                "+" => x.checked_add(y),
+                "-" => x.checked_sub(y),
+                "*" => x.checked_mul(y),
+                "/" => x.checked_div(y),
+                "%" => x.checked_rem(y),
+                "<<" => x.checked_shl((y & mask) as u32),
+                ">>" => x.checked_shr((y & mask) as u32),
+                "&" => Some(x & y),
+                "|" => Some(x | y),
+                "^" => Some(x ^ y),
+                _ => None,
+            }
+        }
+        Expression::Cast(Type::Primitive(ty), x) => Some(wrap(eval(x)?, ty)),
+        Expression::Cast(_, x) => eval(x),
+        _ => None,
+    }
+}
+
+/// Truncate `value` to the range of an integral type, so that `~0` in a
+/// `ulong` enum becomes `ulong.MaxValue`.
+fn wrap(value: i128, ty: &Primitive) -> i128 {
+    match ty {
+        Primitive::Byte => value as u8 as i128,
+        Primitive::Sbyte => value as i8 as i128,
+        Primitive::Short => value as i16 as i128,
+        Primitive::Ushort | Primitive::Char => value as u16 as i128,
+        Primitive::Int => value as i32 as i128,
+        Primitive::Uint => value as u32 as i128,
+        Primitive::Long => value as i64 as i128,
+        Primitive::Ulong => value as u64 as i128,
+        _ => value,
+    }
+}
+
+fn extract_class(
+    queries: &Queries,
+    node: Node,
+    source: &str,
+    parent: Option<&str>,
+) -> Result<Chunk> {
+    let captures = capture(&queries.class, node, source)?;
+
+    let name = text(&captures.required("name")?, source)?.to_string();
+    let name = qualify(parent, name);
+
+    let mods = keywords(captures.all("modifier"), source)?;
+
+    let base = captures
+        .all("base")
+        .iter()
+        .map(|x| decode_base(x, source))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut cls = Class::new(name.clone(), mods, base);
+    cls.set_type_parameters(extract_type_parameters(&node, source)?);
+    cls.set_attributes(extract_attributes(&node, source)?);
+    cls.set_span(span(&node));
+
+    for x in body_declarations(&node) {
+        match x.kind() {
+            "field_declaration" => cls.add_variables(extract_field(queries, x, source)?),
+            "property_declaration" => cls.add_property(extract_property(queries, x, source)?),
+            "constructor_declaration" => {
+                cls.add_constructor(extract_constructor(queries, x, source)?)
+            }
+            "destructor_declaration" => cls.set_finalizer(extract_destructor(x, source)?),
+            "method_declaration" => cls.add_method(extract_method(queries, x, source)?),
+            kind if TYPE_DECLARATIONS.contains(&kind) => {
+                cls.add_nested(extract_chunk(queries, x, source, Some(&name))?)
+            }
+            _ => return Err(unsupported_member(&x)),
+        }
+    }
+
+    Ok(Chunk::Class(cls))
+}
+
+/// Nodes captured by a declaration query, by capture name.
+struct Captures<'a> {
+    node: Node<'a>,
+    nodes: HashMap<String, Vec<Node<'a>>>,
+}
+
+impl<'a> Captures<'a> {
+    fn all(&self, name: &str) -> &[Node<'a>] {
+        self.nodes.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    fn optional(&self, name: &str) -> Option<Node<'a>> {
+        self.all(name).first().copied()
+    }
+
+    fn required(&self, name: &str) -> Result<Node<'a>> {
+        self.optional(name).ok_or_else(|| missing(&self.node, name))
+    }
+}
+
+fn capture<'a>(query: &Query, node: Node<'a>, source: &str) -> Result<Captures<'a>> {
+    // Only match the node itself, not declarations nested further down.
+    let mut cursor = QueryCursor::new();
+    cursor.set_max_start_depth(Some(0));
+
+    // Quantified captures are spread over several matches of the same node,
+    // so merge them all instead of only looking at the first one.
+    let mut res = Vec::new();
+    let mut seen = HashSet::new();
+    let mut matched = false;
+    let mut matches = cursor.matches(query, node, source.as_bytes());
+    while let Some(m) = matches.next() {
+        matched = true;
+        for x in m.captures {
+            if seen.insert((x.index, x.node.id())) {
+                res.push((x.index, x.node));
+            }
+        }
+    }
+    if !matched {
+        return Err(error(&node, "Unexpected declaration shape")
+            .with_hint("The declaration query does not cover this syntax"));
+    }
+    res.sort_by_key(|(_, x)| x.start_byte());
+
+    let nodes = query
+        .capture_names()
+        .iter()
+        .filter_map(|&x| query.capture_index_for_name(x).map(|index| (x, index)))
+        .map(|(x, i)| {
+            (
+                x.to_string(),
+                res.iter()
+                    .filter_map(|(j, y)| if *j == i { Some(*y) } else { None })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect();
+
+    Ok(Captures { node, nodes })
+}
+
+/// One variable per declarator, `int a, b = 1;` yields `a` and `b`.
+fn extract_field(queries: &Queries, node: Node, source: &str) -> Result<Vec<Variable>> {
+    let captures = capture(&queries.field, node, source)?;
+
+    let mods = keywords::<Modifier>(captures.all("modifier"), source)?;
+
+    let ty = decode_type(&captures.required("type")?, source)?;
+
+    let attributes = extract_attributes(&node, source)?;
+
+    let declaration = captures.required("declaration")?;
+
+    declaration
+        .named_children(&mut declaration.walk())
+        .filter(|x| x.kind() == "variable_declarator")
+        .map(|x| {
+            let name = text(&field(&x, "name")?, source)?.to_string();
+
+            // Everything after the name is the initializer, except for the
+            // size of a fixed buffer.
+            let mut ty = ty.clone();
+            let mut value = None;
+            for y in x.named_children(&mut x.walk()).skip(1) {
+                match y.kind() {
+                    "bracketed_argument_list" => {
+                        ty = Type::FixedBuffer(Box::new(ty), decode_fixed_size(&y, source)?)
+                    }
+                    _ => value = Some(decode_expression(&y, source)?),
+                }
+            }
+
+            let mut res = Variable::new(name, mods.clone(), ty, value);
+            res.set_attributes(attributes.clone());
+            res.set_span(span(&x));
+            Ok(res)
+        })
+        .collect()
+}
+
+/// Length of a fixed buffer, the `[16]` in `fixed byte data[16]`.
+fn decode_fixed_size(node: &Node, source: &str) -> Result<usize> {
+    let size = node
+        .named_child(0)
+        .and_then(|x| x.named_child(0))
+        .ok_or_else(|| missing(node, "size"))?;
+    match decode_expression(&size, source)? {
+        Expression::Literal(Literal::Integer(x)) => usize::try_from(x).ok(),
+        _ => None,
+    }
+    .ok_or_else(|| {
+        error(&size, "Unsupported fixed buffer size")
+            .with_hint("Only integer literals are supported as fixed buffer sizes")
+    })
+}
+
+fn extract_property(queries: &Queries, node: Node, source: &str) -> Result<Property> {
+    let captures = capture(&queries.property, node, source)?;
+
+    let name = text(&captures.required("name")?, source)?.to_string();
+
+    let mods = keywords(captures.all("modifier"), source)?;
+
+    let ty = decode_type(&captures.required("type")?, source)?;
+
+    let accessors = captures
+        .all("accessor")
+        .iter()
+        .map(|x| extract_accessor(x, source))
+        .collect::<Result<Vec<_>>>()?;
+
+    // `value` is either `=> expression` or the initializer after the accessors.
+    let (expression, initializer) = match captures.optional("value") {
+        Some(x) if x.kind() == "arrow_expression_clause" => {
+            let expression = x
+                .named_child(0)
+                .ok_or_else(|| error(&x, "Missing expression"))?;
+            (Some(text(&expression, source)?.to_string()), None)
+        }
+        Some(x) => (None, Some(text(&x, source)?.to_string())),
+        None => (None, None),
+    };
+
+    let mut res = Property::new(name, mods, ty, accessors, expression, initializer);
+    res.set_span(span(&node));
+
+    Ok(res)
+}
+
+fn extract_accessor(node: &Node, source: &str) -> Result<Accessor> {
+    let kind = keyword::<AccessorKind>(&field(node, "name")?, source)?;
+
+    let mods = node
+        .named_children(&mut node.walk())
+        .filter(|x| x.kind() == "modifier")
+        .map(|x| keyword(&x, source))
+        .collect::<Result<Vec<_>>>()?;
+
+    let body = node
+        .child_by_field_name("body")
+        .map(|x| Ok(text(&x, source)?.to_string()))
+        .transpose()?;
+
+    Ok(Accessor::new(kind, mods, body))
+}
+
+fn decode_type(node: &Node, source: &str) -> Result<Type> {
+    let res = match node.kind() {
+        // `dynamic` is a contextual keyword, so it parses as a plain name.
+        "identifier" if text(node, source)? == "dynamic" => Type::Primitive(Primitive::Dynamic),
+        "identifier" => Type::Object(text(node, source)?.to_string(), Vec::new()),
+        "predefined_type" => Type::Primitive(keyword(node, source)?),
+        "array_type" => {
+            // `int[][,]` parses as `(int[])[,]`, so the rank specifiers are
+            // collected outermost first and applied innermost first.
+            let mut ranks = Vec::new();
+            let mut element = *node;
+            while element.kind() == "array_type" {
+                let rank = field(&element, "rank")?;
+                ranks.push(
+                    rank.children(&mut rank.walk())
+                        .filter(|x| x.kind() == ",")
+                        .count() as u8
+                        + 1,
+                );
+                element = field(&element, "type")?;
+            }
+            ranks
+                .into_iter()
+                .fold(decode_type(&element, source)?, |ty, rank| {
+                    Type::Array(Box::new(ty), rank)
+                })
+        }
+        "nullable_type" => {
+            let sub_type = decode_type(&field(node, "type")?, source)?;
+            Type::Nullable(Box::new(sub_type))
+        }
+        "pointer_type" => {
+            let sub_type = decode_type(&field(node, "type")?, source)?;
+            match sub_type {
+                Type::Pointer(ty, depth) => Type::Pointer(ty, depth + 1),
+                _ => Type::Pointer(Box::new(sub_type), 1),
+            }
+        }
+        "tuple_type" => {
+            let elements = node
+                .named_children(&mut node.walk())
+                .filter(|x| x.kind() == "tuple_element")
+                .map(|x| {
+                    let name = x
+                        .child_by_field_name("name")
+                        .map(|y| Ok(text(&y, source)?.to_string()))
+                        .transpose()?;
+                    let ty = decode_type(&field(&x, "type")?, source)?;
+                    Ok(TupleElement::new(name, ty))
+                })
+                .collect::<Result<_>>()?;
+            Type::Tuple(elements)
+        }
+        // The calling convention is not kept.
+        "function_pointer_type" => {
+            let parameters = node
+                .named_children(&mut node.walk())
+                .filter(|x| x.kind() == "function_pointer_parameter")
+                .map(|x| {
+                    let ty = decode_type(&field(&x, "type")?, source)?;
+                    let by_ref = x
+                        .children(&mut x.walk())
+                        .any(|y| matches!(y.kind(), "ref" | "out" | "in"));
+                    if by_ref {
+                        Ok(Type::Reference(Box::new(ty)))
+                    } else {
+                        Ok(ty)
+                    }
+                })
+                .collect::<Result<_>>()?;
+            let ret = decode_type(&field(node, "returns")?, source)?;
+            Type::FunctionPointer(parameters, Box::new(ret))
+        }
+        "generic_name" => {
+            let children = node.named_children(&mut node.walk()).collect::<Vec<_>>();
+            let name = children
+                .iter()
+                .find(|&x| x.kind() == "identifier")
+                .ok_or_else(|| error(node, "Missing name"))?;
+            let arguments = children
+                .iter()
+                .find(|x| x.kind() == "type_argument_list")
+                .ok_or_else(|| error(node, "Missing type arguments"))?;
+            let arguments = arguments
+                .named_children(&mut arguments.walk())
+                .map(|x| decode_type(&x, source))
+                .collect::<Result<_>>()?;
+            Type::Object(text(name, source)?.to_string(), arguments)
+        }
+        // `Outer<int>.Inner<string>` keeps the arguments of every segment in
+        // order, the same way the runtime flattens them for nested types.
+        // `global::` only tells the compiler where to start looking, the
+        // name after it is already fully qualified.
+        "alias_qualified_name" if text(&field(node, "alias")?, source)? == "global" => {
+            decode_type(&field(node, "name")?, source)?
+        }
+        "qualified_name" | "alias_qualified_name" => {
+            let (qualifier, separator) = match node.kind() {
+                "qualified_name" => ("qualifier", "."),
+                _ => ("alias", "::"),
+            };
+            let Type::Object(qualifier, mut arguments) =
+                decode_type(&field(node, qualifier)?, source)?
+            else {
+                return Err(error(node, "Qualifier is not a type name"));
+            };
+            let Type::Object(name, rest) = decode_type(&field(node, "name")?, source)? else {
+                return Err(error(node, "Qualified name is not a type name"));
+            };
+            arguments.extend(rest);
+            Type::Object(format!("{}{}{}", qualifier, separator, name), arguments)
+        }
+        "ref_type" => {
+            let sub_type = decode_type(&field(node, "type")?, source)?;
+            Type::Reference(Box::new(sub_type))
+        }
+        _ => {
+            return Err(error(node, "Unsupported type").with_hint(format!(
+                "`decode_type` has no case for `{}` nodes",
+                node.kind()
+            )));
+        }
+    };
+    Ok(res)
+}
+
+/// Value of a literal expression, anything else is kept as written.
+fn decode_literal(node: &Node, source: &str) -> Result<Literal> {
+    let parts = || {
+        node.named_children(&mut node.walk())
+            .map(|x| {
+                let text = text(&x, source)?;
+                match x.kind() {
+                    "escape_sequence" => Ok(unescape(text)),
+                    _ => Ok(text.to_string()),
+                }
+            })
+            .collect::<Result<String>>()
+    };
+    let text = text(node, source)?;
+
+    let res = match node.kind() {
+        "null_literal" => Some(Literal::Null),
+        "boolean_literal" => Some(Literal::Bool(text == "true")),
+        "integer_literal" => parse_integer(text).map(Literal::Integer),
+        "real_literal" => {
+            let kind = match text.chars().last() {
+                Some('f' | 'F') => RealKind::Float,
+                Some('m' | 'M') => RealKind::Decimal,
+                _ => RealKind::Double,
+            };
+            text.replace('_', "")
+                .trim_end_matches(['f', 'F', 'd', 'D', 'm', 'M'])
+                .parse()
+                .ok()
+                .map(|x| Literal::Real(x, kind))
+        }
+        "character_literal" => parts()?.chars().next().map(Literal::Char),
+        "string_literal" => Some(Literal::String(parts()?)),
+        "verbatim_string_literal" => text
+            .strip_prefix("@\"")
+            .and_then(|x| x.strip_suffix('"'))
+            .map(|x| Literal::String(x.replace("\"\"", "\""))),
+        "typeof_expression" => Some(Literal::TypeOf(decode_type(&field(node, "type")?, source)?)),
+        "parenthesized_expression" => node
+            .named_child(0)
+            .map(|x| decode_literal(&x, source))
+            .transpose()?,
+        "prefix_unary_expression" => {
+            match node
+                .named_child(0)
+                .map(|x| decode_literal(&x, source))
+                .transpose()?
+            {
+                Some(Literal::Integer(x)) if text.starts_with('-') => Some(Literal::Integer(-x)),
+                Some(Literal::Real(x, kind)) if text.starts_with('-') => {
+                    Some(Literal::Real(-x, kind))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+
+    Ok(res.unwrap_or_else(|| Literal::Expression(text.to_string())))
+}
+
+fn decode_expression(node: &Node, source: &str) -> Result<Expression> {
+    let expression =
+        |name: &str| Ok::<_, Diagnostic>(Box::new(decode_expression(&field(node, name)?, source)?));
+    let operator = || {
+        node.children(&mut node.walk())
+            .find(|x| !x.is_named())
+            .and_then(|x| x.utf8_text(source.as_bytes()).ok())
+            .map(str::to_string)
+            .ok_or_else(|| error(node, "Missing operator"))
+    };
+    let initializer = |x: Option<Node>| {
+        x.map(|x| {
+            x.named_children(&mut x.walk())
+                .map(|y| decode_expression(&y, source))
+                .collect::<Result<Vec<_>>>()
+        })
+        .transpose()
+        .map(Option::unwrap_or_default)
+    };
+    let initializer_child = || {
+        node.named_children(&mut node.walk())
+            .find(|x| x.kind() == "initializer_expression")
+    };
+    let arguments = |x: Option<Node>| {
+        x.map(|x| decode_arguments(&x, source))
+            .transpose()
+            .map(Option::unwrap_or_default)
+    };
+
+    let res = match node.kind() {
+        "identifier" => Expression::Name(text(node, source)?.to_string()),
+        "parenthesized_expression" => decode_expression(
+            &node
+                .named_child(0)
+                .ok_or_else(|| error(node, "Empty parenthesized expression"))?,
+            source,
+        )?,
+        "member_access_expression" => Expression::MemberAccess(
+            expression("expression")?,
+            text(&field(node, "name")?, source)?.to_string(),
+        ),
+        "prefix_unary_expression" => match decode_literal(node, source)? {
+            Literal::Expression(_) => Expression::Unary(
+                operator()?,
+                Box::new(decode_expression(
+                    &node
+                        .named_child(0)
+                        .ok_or_else(|| error(node, "Missing operand"))?,
+                    source,
+                )?),
+            ),
+            // Negative numbers are folded into the literal.
+            x => Expression::Literal(x),
+        },
+        "binary_expression" | "assignment_expression" => Expression::Binary(
+            expression("left")?,
+            text(&field(node, "operator")?, source)?.to_string(),
+            expression("right")?,
+        ),
+        "conditional_expression" => Expression::Conditional(
+            expression("condition")?,
+            expression("consequence")?,
+            expression("alternative")?,
+        ),
+        "cast_expression" => Expression::Cast(
+            decode_type(&field(node, "type")?, source)?,
+            expression("value")?,
+        ),
+        "invocation_expression" => Expression::Invocation(
+            expression("function")?,
+            decode_arguments(&field(node, "arguments")?, source)?,
+        ),
+        "object_creation_expression" => Expression::New(
+            Some(decode_type(&field(node, "type")?, source)?),
+            arguments(node.child_by_field_name("arguments"))?,
+            initializer(node.child_by_field_name("initializer"))?,
+        ),
+        "implicit_object_creation_expression" => Expression::New(
+            None,
+            arguments(
+                node.named_children(&mut node.walk())
+                    .find(|x| x.kind() == "argument_list"),
+            )?,
+            initializer(initializer_child())?,
+        ),
+        "array_creation_expression" => Expression::Array(
+            Some(decode_type(&field(node, "type")?, source)?),
+            initializer(initializer_child())?,
+        ),
+        "implicit_array_creation_expression" => {
+            Expression::Array(None, initializer(initializer_child())?)
+        }
+        "initializer_expression" => Expression::Array(None, initializer(Some(*node))?),
+        "default_expression" => Expression::Default(
+            node.child_by_field_name("type")
+                .map(|x| decode_type(&x, source))
+                .transpose()?,
+        ),
+        "sizeof_expression" => Expression::SizeOf(decode_type(&field(node, "type")?, source)?),
+        _ => match decode_literal(node, source)? {
+            Literal::Expression(x) => Expression::Other(x),
+            x => Expression::Literal(x),
+        },
+    };
+    Ok(res)
+}
+
+/// Argument values, names and `ref`/`out` are not kept.
+fn decode_arguments(node: &Node, source: &str) -> Result<Vec<Expression>> {
+    node.named_children(&mut node.walk())
+        .filter(|x| x.kind() == "argument")
+        .map(|x| {
+            let value = x
+                .named_children(&mut x.walk())
+                .last()
+                .ok_or_else(|| error(&x, "Empty argument"))?;
+            decode_expression(&value, source)
+        })
+        .collect()
+}
+
+/// Decimal, hex or binary integer with optional digit separators and suffix.
+fn parse_integer(text: &str) -> Option<i128> {
+    let text = text.replace('_', "");
+    let text = text.trim_end_matches(['u', 'U', 'l', 'L']);
+    if let Some(digits) = text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+        i128::from_str_radix(digits, 16).ok()
+    } else if let Some(digits) = text.strip_prefix("0b").or(text.strip_prefix("0B")) {
+        i128::from_str_radix(digits, 2).ok()
+    } else {
+        text.parse().ok()
+    }
+}
+
+fn unescape(sequence: &str) -> String {
+    let res = match sequence {
+        "\\'" => '\'',
+        "\\\"" => '"',
+        "\\\\" => '\\',
+        "\\0" => '\0',
+        "\\a" => '\u{7}',
+        "\\b" => '\u{8}',
+        "\\f" => '\u{c}',
+        "\\n" => '\n',
+        "\\r" => '\r',
+        "\\t" => '\t',
+        "\\v" => '\u{b}',
+        _ => match sequence
+            .get(2..)
+            .and_then(|x| u32::from_str_radix(x, 16).ok())
+        {
+            Some(x) if matches!(&sequence[..2], "\\u" | "\\U" | "\\x") => match char::from_u32(x) {
+                Some(x) => x,
+                None => return sequence.to_string(),
+            },
+            _ => return sequence.to_string(),
+        },
+    };
+    res.to_string()
+}
+
+/// Base types are written as type names, `Object` included.
+fn decode_base(node: &Node, source: &str) -> Result<Type> {
+    let res = match decode_type(node, source)? {
+        Type::Object(name, arguments) if name == "Object" && arguments.is_empty() => {
+            Type::Primitive(Primitive::Object)
+        }
+        ty => ty,
+    };
+    Ok(res)
+}
+
+fn extract_constructor(queries: &Queries, node: Node, source: &str) -> Result<Constructor> {
+    let captures = capture(&queries.constructor, node, source)?;
+
+    let mods = keywords(captures.all("modifier"), source)?;
+
+    let params = extract_parameters(&captures.required("parameters")?, source)?;
+
+    let initializer = captures
+        .optional("initializer")
+        .map(|x| extract_constructor_initializer(&x, source))
+        .transpose()?;
+
+    let body = captures
+        .optional("body")
+        .map(|x| Ok(text(&x, source)?.to_string()))
+        .transpose()?
+        .unwrap_or_default();
+
+    let mut res = Constructor::new(mods, params, initializer, body);
+    res.set_span(span(&node));
+
+    Ok(res)
+}
+
+fn extract_constructor_initializer(node: &Node, source: &str) -> Result<ConstructorInitializer> {
+    let args = node
+        .named_children(&mut node.walk())
+        .find(|x| x.kind() == "argument_list")
+        .map(|x| {
+            x.named_children(&mut x.walk())
+                .map(|y| Ok(text(&y, source)?.to_string()))
+                .collect::<Result<Vec<_>>>()
+        })
+        .transpose()?
+        .unwrap_or_default();
+
+    if node.children(&mut node.walk()).any(|x| x.kind() == "this") {
+        Ok(ConstructorInitializer::This(args))
+    } else {
+        Ok(ConstructorInitializer::Base(args))
+    }
+}
+
+fn extract_destructor(node: Node, source: &str) -> Result<Finalizer> {
+    let body = node
+        .child_by_field_name("body")
+        .map(|x| Ok(text(&x, source)?.to_string()))
+        .transpose()?
+        .unwrap_or_default();
+
+    let mut res = Finalizer::new(body);
+    res.set_span(span(&node));
+    Ok(res)
+}
+
+/// Generic parameters of a type, method or delegate along with their
+/// `where` constraints.
+fn extract_type_parameters(node: &Node, source: &str) -> Result<Vec<TypeParameter>> {
+    let children = node.named_children(&mut node.walk()).collect::<Vec<_>>();
+
+    let Some(list) = children.iter().find(|x| x.kind() == "type_parameter_list") else {
+        return Ok(Vec::new());
+    };
+
+    let clauses = children
+        .iter()
+        .filter(|x| x.kind() == "type_parameter_constraints_clause")
+        .collect::<Vec<_>>();
+
+    list.named_children(&mut list.walk())
+        .filter(|x| x.kind() == "type_parameter")
+        .map(|x| {
+            let name = text(&field(&x, "name")?, source)?.to_string();
+
+            let variance = x
+                .children(&mut x.walk())
+                .find_map(|y| match y.kind() {
+                    "in" => Some(Variance::In),
+                    "out" => Some(Variance::Out),
+                    _ => None,
+                })
+                .unwrap_or_default();
+
+            let constraints = clauses
+                .iter()
+                .filter(|y| {
+                    y.named_child(0)
+                        .and_then(|z| z.utf8_text(source.as_bytes()).ok())
+                        == Some(name.as_str())
+                })
+                .flat_map(|y| y.named_children(&mut y.walk()).skip(1).collect::<Vec<_>>())
+                .map(|y| extract_constraint(&y, source))
+                .collect::<Result<_>>()?;
+
+            Ok(TypeParameter::new(name, variance, constraints))
+        })
+        .collect()
+}
+
+fn extract_constraint(node: &Node, source: &str) -> Result<Constraint> {
+    let text = text(node, source)?;
+    match node.child_by_field_name("type") {
+        // `default` is parsed as a type name.
+        Some(_) if text == "default" => Ok(Constraint::Default),
+        Some(ty) => Ok(Constraint::Type(decode_type(&ty, source)?)),
+        None => Constraint::try_from(text.split_whitespace().collect::<String>().as_str())
+            .map_err(|e| error(node, e)),
+    }
+}
+
+/// Parameters in declaration order, including a trailing `params` array.
+fn extract_parameters(node: &Node, source: &str) -> Result<Vec<Parameter>> {
+    let mut res = node
+        .named_children(&mut node.walk())
+        .filter(|x| x.kind() == "parameter")
+        .map(|x| extract_parameter(&x, source))
+        .collect::<Result<Vec<_>>>()?;
+
+    // `params T[] name` is not wrapped in a `parameter` node.
+    if let (Some(ty), Some(name)) = (
+        node.child_by_field_name("type"),
+        node.child_by_field_name("name"),
+    ) {
+        // Other parameters keep their attributes inside the `parameter` node.
+        let attributes = extract_attributes(node, source)?;
+        res.push(Parameter::new(
+            text(&name, source)?.to_string(),
+            decode_type(&ty, source)?,
+            vec![ParameterModifier::Params],
+            None,
+            attributes,
+        ));
+    }
+
+    Ok(res)
+}
+
+fn extract_parameter(node: &Node, source: &str) -> Result<Parameter> {
+    let ty = node.child_by_field_name("type");
+    let name = field(node, "name")?;
+
+    let mut mods = Vec::new();
+    let mut attributes = Vec::new();
+    let mut default = None;
+    for x in node.named_children(&mut node.walk()) {
+        match x.kind() {
+            "modifier" => mods.push(keyword(&x, source)?),
+            "attribute_list" => attributes.extend(extract_attribute_list(&x, source)?),
+            _ if Some(x) == ty || x == name => {}
+            _ => default = Some(decode_expression(&x, source)?),
+        }
+    }
+
+    Ok(Parameter::new(
+        text(&name, source)?.to_string(),
+        // Only lambda parameters may omit the type.
+        ty.map(|x| decode_type(&x, source))
+            .transpose()?
+            .unwrap_or_default(),
+        mods,
+        default,
+        attributes,
+    ))
+}
+
+/// Attributes in the `attribute_list` children of a declaration.
+fn extract_attributes(node: &Node, source: &str) -> Result<Vec<Attribute>> {
+    let mut res = Vec::new();
+    for x in node
+        .named_children(&mut node.walk())
+        .filter(|x| x.kind() == "attribute_list")
+    {
+        res.extend(extract_attribute_list(&x, source)?);
+    }
+    Ok(res)
+}
+
+fn extract_attribute_list(node: &Node, source: &str) -> Result<Vec<Attribute>> {
+    let target = node
+        .named_children(&mut node.walk())
+        .find(|x| x.kind() == "attribute_target_specifier")
+        .map(|x| Ok(text(&x, source)?.trim_end_matches(':').trim().to_string()))
+        .transpose()?;
+
+    node.named_children(&mut node.walk())
+        .filter(|x| x.kind() == "attribute")
+        .map(|x| extract_attribute(&x, source, target.clone()))
+        .collect()
+}
+
+fn extract_attribute(node: &Node, source: &str, target: Option<String>) -> Result<Attribute> {
+    let name = text(&field(node, "name")?, source)?.to_string();
+
+    let mut arguments = Vec::new();
+    let mut named_arguments = Vec::new();
+    let list = node
+        .named_children(&mut node.walk())
+        .find(|x| x.kind() == "attribute_argument_list");
+    for argument in list
+        .iter()
+        .flat_map(|x| x.named_children(&mut x.walk()).collect::<Vec<_>>())
+        .filter(|x| x.kind() == "attribute_argument")
+    {
+        let children = argument
+            .named_children(&mut argument.walk())
+            .collect::<Vec<_>>();
+        match children.as_slice() {
+            // `size: 16`
+            [name, value] => named_arguments.push((
+                text(name, source)?.to_string(),
+                decode_literal(value, source)?,
+            )),
+            // `Size = 16`
+            [x] if x.kind() == "assignment_expression" => named_arguments.push((
+                text(&field(x, "left")?, source)?.to_string(),
+                decode_literal(&field(x, "right")?, source)?,
+            )),
+            [x] => arguments.push(decode_literal(x, source)?),
+            _ => return Err(error(&argument, "Unexpected attribute argument")),
+        }
+    }
+
+    Ok(Attribute::new(name, target, arguments, named_arguments))
+}
+
+fn extract_method(queries: &Queries, node: Node, source: &str) -> Result<Method> {
+    let captures = capture(&queries.method, node, source)?;
+
+    let name = text(&captures.required("name")?, source)?.to_string();
+
+    let mods = keywords(captures.all("modifier"), source)?;
+
+    let ty = decode_type(&captures.required("type")?, source)?;
+
+    let params = extract_parameters(&captures.required("parameters")?, source)?;
+
+    let body = captures
+        .optional("body")
+        .map(|x| Ok(text(&x, source)?.to_string()))
+        .transpose()?
+        .unwrap_or_default();
+
+    let mut res = Method::new(name, mods, ty, params, body);
+    res.set_type_parameters(extract_type_parameters(&node, source)?);
+    res.set_attributes(extract_attributes(&node, source)?);
+    res.set_span(span(&node));
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decompiler::Fixture;
+    use crate::tests::{find, parse};
+    use crate::types::Member;
+    use crate::workdir::WorkDir;
+
+    fn parse_one(
+        source: &str,
+        on_syntax_error: OnSyntaxError,
+    ) -> std::result::Result<Parsed, ParseError> {
+        let work_dir = WorkDir::create(false).unwrap();
+        let path = work_dir.path().join("A.cs");
+        fs::write(&path, source).unwrap();
+        let file = SourceFile {
+            path,
+            relative: "A.cs".into(),
+        };
+
+        let mut parser = Parser::new();
+        let lang = tree_sitter_c_sharp::LANGUAGE.into();
+        parser.set_language(&lang).unwrap();
+        parse_file(&mut parser, &Queries::new(&lang), &file, on_syntax_error)
+    }
+
+    /// Kind of the diagnostic extracting `source` fails with.
+    fn failure(source: &str) -> String {
+        match parse_one(source, OnSyntaxError::default()) {
+            Err(ParseError::Invalid(x, _)) => x.kind().to_string(),
+            res => panic!("expected an extraction failure, got {:?}", res),
+        }
+    }
+
+    fn enum_values(source: &str, name: &str) -> Vec<Option<i128>> {
+        let units = parse(&Fixture::new().with_file("A.cs", source)).unwrap();
+        match find(&units, name) {
+            Chunk::Enum(x) => x.members().iter().map(|x| x.value()).collect(),
+            _ => panic!("{} is not an enum", name),
+        }
+    }
+
+    fn attribute_arguments(source: &str, name: &str) -> Vec<Literal> {
+        let units = parse(&Fixture::new().with_file("A.cs", source)).unwrap();
+        match find(&units, name) {
+            Chunk::Class(x) => x.attributes()[0].arguments().to_vec(),
+            _ => panic!("{} is not a class", name),
+        }
+    }
+
+    fn fields(source: &str, name: &str) -> Vec<Variable> {
+        let units = parse(&Fixture::new().with_file("A.cs", source)).unwrap();
+        match find(&units, name) {
+            Chunk::Class(x) => x.variables().to_vec(),
+            Chunk::Struct(x) => x.variables().to_vec(),
+            _ => panic!("{} has no fields", name),
+        }
+    }
+
+    /// Members of type `name` in declaration order, constructors as `new`
+    /// and finalizers as `drop`.
+    fn member_names(source: &str, name: &str) -> Vec<String> {
+        let units = parse(&Fixture::new().with_file("A.cs", source)).unwrap();
+        let members = match find(&units, name) {
+            Chunk::Class(x) => x.members(),
+            Chunk::Struct(x) => x.members(),
+            Chunk::Interface(x) => x.members(),
+            _ => panic!("{} has no members", name),
+        };
+        members
+            .iter()
+            .map(|x| match x {
+                Member::Variable(x) => x.name().to_string(),
+                Member::Property(x) => x.name().to_string(),
+                Member::Method(x) => x.name().to_string(),
+                Member::Nested(x) => x.name().to_string(),
+                Member::Constructor(_) => "new".to_string(),
+                Member::Finalizer(_) => "drop".to_string(),
+            })
+            .collect()
+    }
+
+    /// C# spelling of a decoded type, so tests can compare whole types.
+    fn render(ty: &Type) -> String {
+        match ty {
+            Type::Primitive(x) => format!("{:?}", x).to_lowercase(),
+            Type::Array(..) => {
+                let mut ranks = String::new();
+                let mut element = ty;
+                while let Type::Array(x, rank) = element {
+                    ranks += &format!("[{}]", ",".repeat(*rank as usize - 1));
+                    element = x;
+                }
+                render(element) + &ranks
+            }
+            Type::Tuple(elements) => format!(
+                "({})",
+                elements
+                    .iter()
+                    .map(|x| match x.name() {
+                        Some(name) => format!("{} {}", render(x.type_()), name),
+                        None => render(x.type_()),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Type::Reference(x) => format!("ref {}", render(x)),
+            Type::Nullable(x) => format!("{}?", render(x)),
+            Type::Pointer(x, depth) => render(x) + &"*".repeat(*depth as usize),
+            Type::FunctionPointer(parameters, returns) => format!(
+                "delegate*<{}>",
+                parameters
+                    .iter()
+                    .chain([&**returns])
+                    .map(render)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Type::FixedBuffer(x, length) => format!("{}[{}]", render(x), length),
+            Type::Object(name, arguments) if arguments.is_empty() => name.clone(),
+            Type::Object(name, arguments) => format!(
+                "{}<{}>",
+                name,
+                arguments.iter().map(render).collect::<Vec<_>>().join(", ")
+            ),
+        }
+    }
+
+    fn field_types(source: &str, name: &str) -> Vec<String> {
+        fields(source, name)
+            .iter()
+            .map(|x| render(x.type_()))
+            .collect()
+    }
+
+    #[test]
+    fn members_keep_declaration_order() {
+        let names = member_names(
+            "class C { void M() {} int a; C() {} int P { get; set; } ~C() {} int b, c; }",
+            "C",
+        );
+        assert_eq!(names, ["M", "a", "new", "P", "drop", "b", "c"]);
+
+        let names = member_names("struct S { S(int x) {} int P => 1; int a; }", "S");
+        assert_eq!(names, ["new", "P", "a"]);
+
+        let names = member_names("interface I { void M(); int P { get; } }", "I");
+        assert_eq!(names, ["M", "P"]);
+    }
+
+    #[test]
+    fn comments_between_members_are_skipped() {
+        let names = member_names("class C { int a; // note\n /* more */ int b; }", "C");
+        assert_eq!(names, ["a", "b"]);
+    }
+
+    #[test]
+    fn members_after_nested_types_are_kept() {
+        let source = "class C { int a; class N { int x; } int b; struct S {} int c; }";
+        assert_eq!(member_names(source, "C"), ["a", "C.N", "b", "C.S", "c"]);
+        assert_eq!(member_names(source, "C.N"), ["x"]);
+
+        let units = parse(&Fixture::new().with_file(
+            "A.cs",
+            "namespace G { interface I { interface J {} void M(); } }",
+        ))
+        .unwrap();
+        assert!(matches!(find(&units, "G.I.J"), Chunk::Interface(_)));
+        assert_eq!(find(&units, "G.I").nested().len(), 1);
+    }
+
+    #[test]
+    fn unsupported_members_are_reported() {
+        assert_eq!(
+            failure("class C { int a; public event System.Action E; int b; }"),
+            "event_field_declaration"
+        );
+        assert_eq!(
+            failure("class C { int a; int this[int i] => i; int b; }"),
+            "indexer_declaration"
+        );
+        assert_eq!(
+            failure("struct S { public static S operator +(S a, S b) => a; }"),
+            "operator_declaration"
+        );
+        assert_eq!(
+            failure("interface I { int P { get; } event System.Action E; void M(); }"),
+            "event_field_declaration"
+        );
+    }
+
+    #[test]
+    fn integer_literals_accept_every_base_and_suffix() {
+        let arguments = attribute_arguments("[A(0x1F, 0B101, 1_000UL, 42u, -5)] class C {}", "C");
+        let values = arguments
+            .iter()
+            .map(|x| match x {
+                Literal::Integer(x) => *x,
+                x => panic!("{:?} is not an integer", x),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(values, [31, 5, 1000, 42, -5]);
+    }
+
+    #[test]
+    fn literals_are_decoded() {
+        let arguments = attribute_arguments(
+            r#"[A(null, true, 'c', '\n', "a\tb\u0041", @"x""y", typeof(int))] class C {}"#,
+            "C",
+        );
+        assert!(matches!(arguments[0], Literal::Null));
+        assert!(matches!(arguments[1], Literal::Bool(true)));
+        assert!(matches!(arguments[2], Literal::Char('c')));
+        assert!(matches!(arguments[3], Literal::Char('\n')));
+        assert!(matches!(&arguments[4], Literal::String(x) if x == "a\tbA"));
+        assert!(matches!(&arguments[5], Literal::String(x) if x == "x\"y"));
+        assert!(matches!(
+            arguments[6],
+            Literal::TypeOf(Type::Primitive(Primitive::Int))
+        ));
+    }
+
+    #[test]
+    fn real_literals_keep_their_kind() {
+        let arguments = attribute_arguments("[A(0.5f, 0.5, 0.5d, 0.5m, -1.5F)] class C {}", "C");
+        let kinds = arguments
+            .iter()
+            .map(|x| match x {
+                Literal::Real(x, kind) => (*x, *kind),
+                x => panic!("{:?} is not a real", x),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                (0.5, RealKind::Float),
+                (0.5, RealKind::Double),
+                (0.5, RealKind::Double),
+                (0.5, RealKind::Decimal),
+                (-1.5, RealKind::Float),
+            ]
+        );
+    }
+
+    #[test]
+    fn field_initializers_are_expressions() {
+        let fields = fields("class C { int a = 1 << 4; string b = null, c; }", "C");
+        assert!(matches!(fields[0].value(), Some(Expression::Binary(_, op, _)) if op == "<<"));
+        assert!(matches!(
+            fields[1].value(),
+            Some(Expression::Literal(Literal::Null))
+        ));
+        assert!(fields[2].value().is_none());
+    }
+
+    #[test]
+    fn fixed_buffers_keep_their_length() {
+        let fields = fields(
+            "unsafe struct S { public fixed byte data[16]; volatile int after; }",
+            "S",
+        );
+        assert_eq!(fields[0].name(), "data");
+        assert!(fields[0].modifiers().contains(&Modifier::Fixed));
+        assert!(matches!(
+            fields[0].type_(),
+            Type::FixedBuffer(x, 16) if matches!(**x, Type::Primitive(Primitive::Byte))
+        ));
+        assert!(fields[0].value().is_none());
+        assert!(fields[1].modifiers().contains(&Modifier::Volatile));
+    }
+
+    #[test]
+    fn enum_values_count_up_from_the_previous_member() {
+        let values = enum_values("enum E { A, B, C = 10, D, E = C }", "E");
+        assert_eq!(values, [Some(0), Some(1), Some(10), Some(11), Some(10)]);
+    }
+
+    #[test]
+    fn enum_values_evaluate_constant_expressions() {
+        let values = enum_values(
+            "enum E { A = 1 << 4, B = A | 0x3, C = (B - 1) * 2, D = E.A + 'a', F = -1 }",
+            "E",
+        );
+        assert_eq!(values, [Some(16), Some(19), Some(36), Some(113), Some(-1)]);
+    }
+
+    #[test]
+    fn enum_values_wrap_to_the_underlying_type() {
+        let values = enum_values("enum E : byte { A = ~0, B = 255 + 2 }", "E");
+        assert_eq!(values, [Some(255), Some(1)]);
+
+        let values = enum_values("enum E : ulong { All = ~0UL }", "E");
+        assert_eq!(values, [Some(u64::MAX as i128)]);
+
+        let values = enum_values("enum E : System.SByte { A = 0x80 }", "E");
+        assert_eq!(values, [Some(-128)]);
+    }
+
+    #[test]
+    fn enum_shift_counts_are_masked_to_the_operand_width() {
+        let values = enum_values("enum E { A = 1 << 32, B = 1 << 33, C = 8 >> 35 }", "E");
+        assert_eq!(values, [Some(1), Some(2), Some(1)]);
+
+        let values = enum_values(
+            "enum E : long { A = 1L << 32, B = 1L << 64, C = -(1 << 127) }",
+            "E",
+        );
+        assert_eq!(values, [Some(1 << 32), Some(1), Some(i64::MIN as i128)]);
+    }
+
+    #[test]
+    fn enum_values_are_unknown_after_external_constants() {
+        let values = enum_values("enum E { A, B = Other.Value, C, D = 4 }", "E");
+        assert_eq!(values, [Some(0), None, None, Some(4)]);
+    }
+
+    fn parameters(source: &str, name: &str) -> Vec<Parameter> {
+        let units = parse(&Fixture::new().with_file("A.cs", source)).unwrap();
+        match find(&units, name) {
+            Chunk::Class(x) => x.methods()[0].parameters().to_vec(),
+            Chunk::Delegate(x) => x.parameters().to_vec(),
+            _ => panic!("{} has no method", name),
+        }
+    }
+
+    #[test]
+    fn parameters_keep_order_and_modifiers() {
+        let method = parameters(
+            "static class C { static void M(this int a, ref int b, out int c, in int d, params int[] e) {} }",
+            "C",
+        );
+        let names = method.iter().map(|x| x.name()).collect::<Vec<_>>();
+        assert_eq!(names, ["a", "b", "c", "d", "e"]);
+        assert!(method[0].has_modifier(ParameterModifier::This));
+        assert!(method[1].has_modifier(ParameterModifier::Ref));
+        assert!(method[2].has_modifier(ParameterModifier::Out));
+        assert!(method[3].has_modifier(ParameterModifier::In));
+        assert!(method[4].has_modifier(ParameterModifier::Params));
+        assert!(matches!(method[4].type_(), Type::Array(_, 1)));
+
+        let delegate = parameters("delegate void D([In] int a, string b);", "D");
+        assert_eq!(delegate.len(), 2);
+        assert_eq!(delegate[0].attributes()[0].name(), "In");
+    }
+
+    #[test]
+    fn parameter_defaults_are_expressions() {
+        let defaults = parameters(
+            "class C { void M(int a = -1, Color c = Color.red, string s = null, int d) {} }",
+            "C",
+        );
+        assert!(matches!(
+            defaults[0].default(),
+            Some(Expression::Literal(Literal::Integer(-1)))
+        ));
+        assert!(matches!(
+            defaults[1].default(),
+            Some(Expression::MemberAccess(x, name))
+                if name == "red" && matches!(x.as_ref(), Expression::Name(x) if x == "Color")
+        ));
+        assert!(matches!(
+            defaults[2].default(),
+            Some(Expression::Literal(Literal::Null))
+        ));
+        assert!(defaults[3].default().is_none());
+    }
+
+    #[test]
+    fn predefined_types_and_modifiers_are_accepted() {
+        let fields = fields(
+            "public partial class C { decimal a; nint b; nuint c; dynamic d; required public int e; }",
+            "C",
+        );
+        let types = fields
+            .iter()
+            .map(|x| match x.type_() {
+                Type::Primitive(x) => x.clone(),
+                x => panic!("{:?} is not a primitive", x),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            [
+                Primitive::Decimal,
+                Primitive::Nint,
+                Primitive::Nuint,
+                Primitive::Dynamic,
+                Primitive::Int,
+            ]
+        );
+        assert!(fields[4].modifiers().contains(&Modifier::Required));
+    }
+
+    #[test]
+    fn every_declaration_in_a_file_is_extracted() {
+        let units = parse(&Fixture::new().with_file(
+            "A.cs",
+            "namespace G {
+                class A {}
+                enum B { X }
+                struct C {}
+                interface D {}
+                delegate void E();
+            }
+            namespace H { class F {} }",
+        ))
+        .unwrap();
+        let names = units[0]
+            .chunks()
+            .iter()
+            .map(|x| x.full_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["G.A", "G.B", "G.C", "G.D", "G.E", "H.F"]);
+    }
+
+    #[test]
+    fn records_are_reported() {
+        assert_eq!(failure("record R(int X);"), "record_declaration");
+        assert_eq!(
+            failure("namespace G { record struct R; }"),
+            "record_declaration"
+        );
+    }
+
+    #[test]
+    fn namespaces_nest() {
+        let units = parse(&Fixture::new().with_file(
+            "A.cs",
+            "namespace A { namespace B.C { class X { class Y {} } } class Z {} }",
+        ))
+        .unwrap();
+        assert_eq!(find(&units, "A.B.C.X").namespace(), Some("A.B.C"));
+        assert_eq!(find(&units, "A.B.C.X.Y").namespace(), Some("A.B.C"));
+        assert_eq!(find(&units, "A.Z").namespace(), Some("A"));
+    }
+
+    #[test]
+    fn file_scoped_namespaces_cover_the_rest_of_the_file() {
+        let units = parse(
+            &Fixture::new().with_file("A.cs", "using System; namespace A.B; class X {} enum E {}"),
+        )
+        .unwrap();
+        find(&units, "A.B.X");
+        find(&units, "A.B.E");
+    }
+
+    fn properties(source: &str, name: &str) -> Vec<Property> {
+        let units = parse(&Fixture::new().with_file("A.cs", source)).unwrap();
+        match find(&units, name) {
+            Chunk::Class(x) => x.properties().to_vec(),
+            Chunk::Interface(x) => x.properties().to_vec(),
+            _ => panic!("{} has no properties", name),
+        }
+    }
+
+    #[test]
+    fn properties_keep_accessors_and_initializers() {
+        let properties = properties(
+            "class C {
+                public int A { get; private set; } = 5;
+                int B => a + 1;
+                int D { get { return 1; } init => x = value; }
+            }",
+            "C",
+        );
+
+        let a = &properties[0];
+        assert_eq!(a.name(), "A");
+        assert!(a.is_auto());
+        assert_eq!(a.initializer(), Some("5"));
+        let set = a.accessor(AccessorKind::Set).unwrap();
+        assert_eq!(set.modifiers(), [Modifier::Private]);
+        assert!(set.body().is_none());
+
+        let b = &properties[1];
+        assert!(!b.is_auto());
+        assert!(b.accessors().is_empty());
+        assert_eq!(b.expression(), Some("a + 1"));
+
+        let d = &properties[2];
+        assert!(!d.is_auto());
+        assert_eq!(
+            d.accessor(AccessorKind::Get).unwrap().body(),
+            Some("{ return 1; }")
+        );
+        assert!(d.accessor(AccessorKind::Init).is_some());
+        assert!(d.accessor(AccessorKind::Set).is_none());
+    }
+
+    #[test]
+    fn interface_properties_have_no_bodies() {
+        let properties = properties("interface I { int P { get; set; } }", "I");
+        assert_eq!(properties[0].accessors().len(), 2);
+        assert!(properties[0].is_auto());
+    }
+
+    #[test]
+    fn constructors_keep_initializers_and_finalizers() {
+        let units = parse(&Fixture::new().with_file(
+            "A.cs",
+            "class C {
+                static C() { Count = 0; }
+                public C(int a, string b) : base(a, b) {}
+                C() : this(1, \"x\") {}
+                ~C() { Release(); }
+            }",
+        ))
+        .unwrap();
+        let Chunk::Class(class) = find(&units, "C") else {
+            panic!("C is not a class");
+        };
+
+        let constructors = class.constructors();
+        assert_eq!(constructors.len(), 2);
+        assert_eq!(constructors[0].modifiers(), [Modifier::Public]);
+        assert_eq!(constructors[0].parameters().len(), 2);
+        assert!(matches!(
+            constructors[0].initializer(),
+            Some(ConstructorInitializer::Base(x)) if x == &["a", "b"]
+        ));
+        assert!(matches!(
+            constructors[1].initializer(),
+            Some(ConstructorInitializer::This(x)) if x == &["1", "\"x\""]
+        ));
+
+        let static_constructor = class.static_constructor().unwrap();
+        assert!(static_constructor.is_static());
+        assert!(static_constructor.initializer().is_none());
+        assert_eq!(static_constructor.body(), "{ Count = 0; }");
+
+        assert_eq!(class.finalizer().unwrap().body(), "{ Release(); }");
+    }
+
+    #[test]
+    fn type_parameters_keep_variance_and_constraints() {
+        let units = parse(&Fixture::new().with_file(
+            "A.cs",
+            "interface I<in T, out U> {}
+            class C<T, U> where T : class?, IComparable<T>, new() where U : unmanaged {
+                void M<V>() where V : struct {}
+            }",
+        ))
+        .unwrap();
+
+        let Chunk::Interface(interface) = find(&units, "I") else {
+            panic!("I is not an interface");
+        };
+        let variances = interface
+            .type_parameters()
+            .iter()
+            .map(|x| x.variance())
+            .collect::<Vec<_>>();
+        assert_eq!(variances, [Variance::In, Variance::Out]);
+
+        let Chunk::Class(class) = find(&units, "C") else {
+            panic!("C is not a class");
+        };
+        let [t, u] = class.type_parameters() else {
+            panic!("C should have two type parameters");
+        };
+        assert_eq!(t.variance(), Variance::Invariant);
+        assert!(matches!(
+            t.constraints(),
+            [
+                Constraint::NullableClass,
+                Constraint::Type(Type::Object(name, arguments)),
+                Constraint::New,
+            ] if name == "IComparable" && arguments.len() == 1
+        ));
+        assert!(matches!(u.constraints(), [Constraint::Unmanaged]));
+
+        let method = &class.methods()[0].type_parameters()[0];
+        assert_eq!(method.name(), "V");
+        assert!(matches!(method.constraints(), [Constraint::Struct]));
+    }
+
+    #[test]
+    fn generic_arguments_nest() {
+        let types = field_types(
+            "class C {
+                Dictionary<string, List<int>> a;
+                System.Collections.Generic.List<Game.Item> b;
+                Func<List<Dictionary<int, string>>, Action<int>> c;
+            }",
+            "C",
+        );
+        assert_eq!(
+            types,
+            [
+                "Dictionary<string, List<int>>",
+                "System.Collections.Generic.List<Game.Item>",
+                "Func<List<Dictionary<int, string>>, Action<int>>",
+            ]
+        );
+    }
+
+    #[test]
+    fn compound_types_are_decoded() {
+        let types = field_types(
+            "unsafe class C {
+                (int id, string) a;
+                int** b;
+                int[,] c;
+                int[][,] d;
+                Item?[] e;
+                int? f;
+                delegate*<int, string, void> g;
+            }",
+            "C",
+        );
+        assert_eq!(
+            types,
+            [
+                "(int id, string)",
+                "int**",
+                "int[,]",
+                "int[][,]",
+                "Item?[]",
+                "int?",
+                "delegate*<int, string, void>",
+            ]
+        );
+    }
+
+    #[test]
+    fn declarators_share_type_and_modifiers() {
+        let fields = fields("class C { private static int a, b = 2, c; string d; }", "C");
+        let names = fields.iter().map(|x| x.name()).collect::<Vec<_>>();
+        assert_eq!(names, ["a", "b", "c", "d"]);
+        for field in &fields[..3] {
+            assert_eq!(render(field.type_()), "int");
+            assert_eq!(field.modifiers(), [Modifier::Private, Modifier::Static]);
+        }
+        assert!(fields[0].value().is_none());
+        assert!(fields[1].value().is_some());
+        assert!(fields[2].value().is_none());
+        assert!(fields[3].modifiers().is_empty());
+    }
+
+    #[test]
+    fn diagnostics_report_their_full_span() {
+        let Err(ParseError::Invalid(x, _)) = parse_one(
+            "class C {\n    event Action E;\n}",
+            OnSyntaxError::default(),
+        ) else {
+            panic!("the event should fail extraction");
+        };
+        let json = x.to_json();
+        assert_eq!(json["start"], 14);
+        assert_eq!(json["end"], 29);
+        assert_eq!(json["line"], 2);
+        assert_eq!(json["column"], 5);
+        assert_eq!(json["end_line"], 2);
+        assert_eq!(json["end_column"], 20);
+        assert_eq!(json["source"], "    event Action E;");
+    }
+
+    #[test]
+    fn best_effort_keeps_syntax_errors() {
+        let source = "class A { int a = 1 +; int b; }";
+
+        let parsed = parse_one(source, OnSyntaxError::BestEffort).unwrap();
+        assert!(parsed.unit.is_some());
+        assert_eq!(parsed.syntax_errors.len(), 1);
+        assert_eq!(parsed.syntax_errors[0].severity(), Severity::Warning);
+        assert_eq!(parsed.syntax_errors[0].span().line(), 1);
+
+        let parsed = parse_one(source, OnSyntaxError::Skip).unwrap();
+        assert!(parsed.unit.is_none());
+        assert_eq!(parsed.syntax_errors.len(), 1);
+
+        match parse_one(source, OnSyntaxError::Fail) {
+            Err(ParseError::Syntax(x)) => assert_eq!(x[0].severity(), Severity::Error),
+            res => panic!("expected a syntax failure, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn extraction_failures_keep_earlier_syntax_errors() {
+        match parse_one(
+            "class A { int a = 1 +; event Action E; }",
+            OnSyntaxError::BestEffort,
+        ) {
+            Err(ParseError::Invalid(x, syntax_errors)) => {
+                assert_eq!(x.kind(), "event_field_declaration");
+                assert_eq!(syntax_errors.len(), 1);
+            }
+            res => panic!("expected an extraction failure, got {:?}", res),
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use tree_sitter::{Language, Query};
+
+/// Every query the parser runs, compiled once and shared by all files.
+///
+/// `Query` is immutable after compilation, so one registry can be used from
+/// any number of threads.
+#[derive(Debug)]
+pub struct Queries {
+    /// Top level and nested type declarations of a file.
+    pub types: Query,
+    /// Using directives, including those inside namespace blocks.
+    pub usings: Query,
+    pub class: Query,
+    pub struct_: Query,
+    pub interface: Query,
+    pub enum_: Query,
+    pub delegate: Query,
+    pub field: Query,
+    pub property: Query,
+    pub constructor: Query,
+    pub method: Query,
+}
+
+impl Queries {
+    pub fn new(lang: &Language) -> Self {
+        Queries {
+            types: compile(lang, TYPES),
+            usings: compile(lang, USINGS),
+            class: compile(lang, CLASS),
+            struct_: compile(lang, STRUCT),
+            interface: compile(lang, INTERFACE),
+            enum_: compile(lang, ENUM),
+            delegate: compile(lang, DELEGATE),
+            field: compile(lang, FIELD),
+            property: compile(lang, PROPERTY),
+            constructor: compile(lang, CONSTRUCTOR),
+            method: compile(lang, METHOD),
+        }
+    }
+}
+
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Queries>();
+};
+
+fn compile(lang: &Language, source: &str) -> Query {
+    Query::new(lang, source).expect("Invalid query")
+}
+
+const TYPES: &str = r#"
+[
+    (class_declaration)
+    (enum_declaration)
+    (struct_declaration)
+    (interface_declaration)
+    (delegate_declaration)
+    (record_declaration)
+] @type
+"#;
+
+const USINGS: &str = "(using_directive) @using";
+
+const CLASS: &str = r#"
+(class_declaration
+    (modifier)* @modifier
+    name: (identifier) @name
+    (base_list
+        [
+            (identifier)
+            (generic_name)
+            (qualified_name)
+            (alias_qualified_name)
+        ] @base
+    )?
+)
+"#;
+
+const STRUCT: &str = r#"
+(struct_declaration
+    (modifier)* @modifier
+    (identifier) @name
+    (base_list
+        [
+            (identifier)
+            (generic_name)
+            (qualified_name)
+            (alias_qualified_name)
+        ] @base
+    )?
+)
+"#;
+
+const INTERFACE: &str = r#"
+(interface_declaration
+    (modifier)* @modifier
+    (identifier) @name
+    (base_list
+        [
+            (identifier)
+            (generic_name)
+            (qualified_name)
+            (alias_qualified_name)
+        ] @base
+    )?
+)
+"#;
+
+const ENUM: &str = r#"
+(enum_declaration
+    (modifier)* @modifier
+    (identifier) @name
+    (base_list
+        [
+            (predefined_type)
+            (identifier)
+            (qualified_name)
+        ] @base
+    )?
+    (enum_member_declaration_list) @members
+)
+"#;
+
+const DELEGATE: &str = r#"
+(delegate_declaration
+    (modifier)* @modifier
+    type: (_) @type
+    name: (identifier) @name
+    parameters: (parameter_list) @parameters
+    body: (block)? @body
+)
+"#;
+
+const FIELD: &str = r#"
+(field_declaration
+    (modifier)* @modifier
+    (variable_declaration
+        type: (_) @type
+    ) @declaration
+)
+"#;
+
+const PROPERTY: &str = r#"
+(property_declaration
+    (modifier)* @modifier
+    type: (_) @type
+    name: (identifier) @name
+    accessors: (accessor_list
+        (accessor_declaration)* @accessor
+    )?
+    value: (_)? @value
+)
+"#;
+
+const CONSTRUCTOR: &str = r#"
+(constructor_declaration
+    (modifier)* @modifier
+    parameters: (parameter_list) @parameters
+    (constructor_initializer)? @initializer
+    body: (_)? @body
+)
+"#;
+
+const METHOD: &str = r#"
+(method_declaration
+    (modifier)* @modifier
+    returns: (_) @type
+    name: (identifier) @name
+    parameters: (parameter_list) @parameters
+    body: (block)? @body
+)
+"#;
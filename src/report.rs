@@ -0,0 +1,114 @@
+use std::fmt;
+
+use serde_json::{Value, json};
+
+use crate::diagnostic::{Diagnostic, ParseError};
+
+/// Outcome of parsing a batch of source files when failures do not stop the run.
+#[derive(Debug, Default)]
+pub struct Report {
+    parsed: usize,
+    skipped: usize,
+    failures: Vec<ParseError>,
+    /// Syntax errors in files that were extracted or skipped anyway.
+    syntax_errors: Vec<Diagnostic>,
+}
+
+impl Report {
+    pub fn new() -> Self {
+        Report::default()
+    }
+
+    pub fn add_parsed(&mut self) {
+        self.parsed += 1;
+    }
+
+    pub fn add_skipped(&mut self) {
+        self.skipped += 1;
+    }
+
+    pub fn add_syntax_errors(&mut self, errors: Vec<Diagnostic>) {
+        self.syntax_errors.extend(errors);
+    }
+
+    pub fn add_failure(&mut self, error: ParseError) {
+        self.failures.push(error);
+    }
+
+    pub fn parsed(&self) -> usize {
+        self.parsed
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    pub fn failed(&self) -> usize {
+        self.failures.len()
+    }
+
+    pub fn failures(&self) -> &[ParseError] {
+        &self.failures
+    }
+
+    pub fn syntax_errors(&self) -> &[Diagnostic] {
+        &self.syntax_errors
+    }
+
+    /// Failures grouped by the construct they stopped at, most frequent first.
+    pub fn by_construct(&self) -> Vec<(&str, Vec<&ParseError>)> {
+        let mut res: Vec<(&str, Vec<&ParseError>)> = Vec::new();
+        for x in &self.failures {
+            match res.iter_mut().find(|(kind, _)| *kind == x.kind()) {
+                Some((_, group)) => group.push(x),
+                None => res.push((x.kind(), vec![x])),
+            }
+        }
+        // Stable, so equally frequent constructs keep the order they were first seen in.
+        res.sort_by_key(|(_, group)| std::cmp::Reverse(group.len()));
+        res
+    }
+
+    pub fn to_json(&self) -> Value {
+        let constructs = self
+            .by_construct()
+            .into_iter()
+            .map(|(kind, group)| {
+                json!({
+                    "kind": kind,
+                    "count": group.len(),
+                    "failures": group.iter().map(|x| x.to_json()).collect::<Vec<_>>(),
+                })
+            })
+            .collect::<Vec<_>>();
+        json!({
+            "parsed": self.parsed,
+            "skipped": self.skipped,
+            "failed": self.failed(),
+            "constructs": constructs,
+            "syntax_errors": self.syntax_errors.iter().map(|x| x.to_json()).collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Parsed {} files, {} skipped, {} failed",
+            self.parsed,
+            self.skipped,
+            self.failed()
+        )?;
+        if !self.syntax_errors.is_empty() {
+            write!(f, "\n  syntax errors: {}", self.syntax_errors.len())?;
+        }
+        for (kind, group) in self.by_construct() {
+            write!(f, "\n  {}: {}", kind, group.len())?;
+            for x in group {
+                write!(f, "\n    {}", x)?;
+            }
+        }
+        Ok(())
+    }
+}
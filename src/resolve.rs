@@ -0,0 +1,625 @@
+use std::collections::HashSet;
+
+use crate::types::{Chunk, CompilationUnit, Type, Using};
+
+/// Commonly referenced types outside the decompiled assembly, by namespace.
+///
+/// Generic types carry their arity the way the runtime names them, e.g.
+/// ``List`1``, ``Func`*`` stands for any arity but zero.
+const FRAMEWORK_TYPES: &[(&str, &[&str])] = &[
+    (
+        "System",
+        &[
+            "Action",
+            "Action`*",
+            "Activator",
+            "Array",
+            "ArgumentException",
+            "ArgumentNullException",
+            "ArgumentOutOfRangeException",
+            "Attribute",
+            "BitConverter",
+            "Boolean",
+            "Buffer",
+            "Byte",
+            "Char",
+            "Comparison`1",
+            "Console",
+            "Convert",
+            "DateTime",
+            "Decimal",
+            "Delegate",
+            "Double",
+            "Enum",
+            "Environment",
+            "EventArgs",
+            "EventHandler",
+            "EventHandler`1",
+            "Exception",
+            "Func`*",
+            "GC",
+            "Guid",
+            "IComparable",
+            "IComparable`1",
+            "IDisposable",
+            "IEquatable`1",
+            "Int16",
+            "Int32",
+            "Int64",
+            "IntPtr",
+            "InvalidOperationException",
+            "Math",
+            "MathF",
+            "NotImplementedException",
+            "NotSupportedException",
+            "Nullable",
+            "Nullable`1",
+            "Object",
+            "Predicate`1",
+            "Random",
+            "SByte",
+            "Single",
+            "String",
+            "StringComparison",
+            "TimeSpan",
+            "Tuple",
+            "Tuple`*",
+            "Type",
+            "UInt16",
+            "UInt32",
+            "UInt64",
+            "UIntPtr",
+            "ValueTuple",
+            "ValueTuple`*",
+            "ValueType",
+            "Version",
+        ],
+    ),
+    (
+        "System.Collections",
+        &[
+            "ArrayList",
+            "BitArray",
+            "Hashtable",
+            "ICollection",
+            "IComparer",
+            "IEnumerable",
+            "IEnumerator",
+            "IList",
+            "Queue",
+            "Stack",
+        ],
+    ),
+    (
+        "System.Collections.Generic",
+        &[
+            "Comparer`1",
+            "Dictionary`2",
+            "EqualityComparer`1",
+            "HashSet`1",
+            "ICollection`1",
+            "IComparer`1",
+            "IDictionary`2",
+            "IEnumerable`1",
+            "IEnumerator`1",
+            "IEqualityComparer`1",
+            "IList`1",
+            "IReadOnlyCollection`1",
+            "IReadOnlyDictionary`2",
+            "IReadOnlyList`1",
+            "ISet`1",
+            "KeyValuePair`2",
+            "LinkedList`1",
+            "LinkedListNode`1",
+            "List`1",
+            "Queue`1",
+            "SortedDictionary`2",
+            "SortedList`2",
+            "SortedSet`1",
+            "Stack`1",
+        ],
+    ),
+    (
+        "System.Collections.Concurrent",
+        &[
+            "ConcurrentDictionary`2",
+            "ConcurrentQueue`1",
+            "ConcurrentBag`1",
+        ],
+    ),
+    (
+        "System.IO",
+        &[
+            "BinaryReader",
+            "BinaryWriter",
+            "Directory",
+            "DirectoryInfo",
+            "File",
+            "FileInfo",
+            "FileMode",
+            "FileStream",
+            "MemoryStream",
+            "Path",
+            "SeekOrigin",
+            "Stream",
+            "StreamReader",
+            "StreamWriter",
+            "StringReader",
+            "StringWriter",
+            "TextReader",
+            "TextWriter",
+        ],
+    ),
+    ("System.Text", &["Encoding", "StringBuilder"]),
+    ("System.Text.RegularExpressions", &["Match", "Regex"]),
+    (
+        "System.Linq",
+        &[
+            "Enumerable",
+            "IGrouping`2",
+            "ILookup`2",
+            "IOrderedEnumerable`1",
+        ],
+    ),
+    (
+        "System.Threading",
+        &[
+            "AutoResetEvent",
+            "CancellationToken",
+            "Interlocked",
+            "ManualResetEvent",
+            "Monitor",
+            "Mutex",
+            "ParameterizedThreadStart",
+            "Semaphore",
+            "SpinLock",
+            "Thread",
+            "ThreadPool",
+            "ThreadStart",
+            "Timer",
+            "WaitCallback",
+        ],
+    ),
+    ("System.Threading.Tasks", &["Parallel", "Task", "Task`1"]),
+    (
+        "System.Diagnostics",
+        &["Debug", "Process", "Stopwatch", "Trace"],
+    ),
+    ("System.Globalization", &["CultureInfo", "NumberStyles"]),
+    (
+        "System.Reflection",
+        &[
+            "Assembly",
+            "BindingFlags",
+            "FieldInfo",
+            "MethodInfo",
+            "PropertyInfo",
+        ],
+    ),
+    (
+        "System.Runtime.InteropServices",
+        &[
+            "CharSet",
+            "FieldOffsetAttribute",
+            "GCHandle",
+            "LayoutKind",
+            "Marshal",
+            "StructLayoutAttribute",
+        ],
+    ),
+    (
+        "UnityEngine",
+        &[
+            "Animator",
+            "Application",
+            "AudioClip",
+            "AudioSource",
+            "Behaviour",
+            "Bounds",
+            "Camera",
+            "Collider",
+            "Color",
+            "Color32",
+            "Component",
+            "Coroutine",
+            "Debug",
+            "GameObject",
+            "Gradient",
+            "Input",
+            "KeyCode",
+            "Light",
+            "LayerMask",
+            "Material",
+            "Mathf",
+            "Matrix4x4",
+            "Mesh",
+            "MeshFilter",
+            "MeshRenderer",
+            "MonoBehaviour",
+            "Object",
+            "Physics",
+            "Quaternion",
+            "Random",
+            "Ray",
+            "RaycastHit",
+            "Rect",
+            "RectTransform",
+            "Renderer",
+            "Resources",
+            "Rigidbody",
+            "ScriptableObject",
+            "Shader",
+            "Sprite",
+            "Texture",
+            "Texture2D",
+            "Time",
+            "Transform",
+            "Vector2",
+            "Vector2Int",
+            "Vector3",
+            "Vector3Int",
+            "Vector4",
+        ],
+    ),
+    (
+        "UnityEngine.UI",
+        &["Button", "Image", "RawImage", "Slider", "Text", "Toggle"],
+    ),
+];
+
+/// Where a fully qualified type name comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    /// Declared in the parsed sources.
+    Game,
+    /// Part of the .NET or Unity libraries.
+    Framework,
+    Unknown,
+}
+
+/// Resolves bare type names against the declared types and using directives.
+#[derive(Debug, Default)]
+pub struct Resolver {
+    /// Full names of the declared types along with their number of type parameters.
+    known: HashSet<(String, usize)>,
+}
+
+impl Resolver {
+    pub fn new(units: &[CompilationUnit]) -> Self {
+        let known = units
+            .iter()
+            .flat_map(|x| x.chunks())
+            .flat_map(|x| x.flatten())
+            .map(|x| (x.full_name(), x.type_parameters().len()))
+            .collect();
+        Resolver { known }
+    }
+
+    /// Where the type `name` with `arity` type arguments is declared.
+    pub fn origin(&self, name: &str, arity: usize) -> Origin {
+        if self.is_known(name, arity) {
+            return Origin::Game;
+        }
+        match name.rsplit_once('.') {
+            Some((namespace, name)) if is_framework(namespace, name, arity) => Origin::Framework,
+            _ => Origin::Unknown,
+        }
+    }
+
+    /// Origin of every named type referenced by the chunks `select` accepts,
+    /// nested ones included. Primitives and type parameters are left out.
+    pub fn origins(&self, chunk: &Chunk, select: &dyn Fn(&Chunk) -> bool) -> Vec<Origin> {
+        let mut res = Vec::new();
+        self.collect_origins(chunk, &[], select, &mut res);
+        res
+    }
+
+    fn collect_origins(
+        &self,
+        chunk: &Chunk,
+        outer: &[String],
+        select: &dyn Fn(&Chunk) -> bool,
+        res: &mut Vec<Origin>,
+    ) {
+        let generics = in_scope(outer, chunk);
+        if select(chunk) {
+            for (declared, types) in chunk.scoped_types() {
+                let generics = [generics.as_slice(), &declared].concat();
+                for ty in types {
+                    self.type_origins(ty, &generics, res);
+                }
+            }
+        }
+        for nested in chunk.nested() {
+            self.collect_origins(nested, &generics, select, res);
+        }
+    }
+
+    fn type_origins(&self, ty: &Type, generics: &[String], res: &mut Vec<Origin>) {
+        match ty {
+            Type::Primitive(_) => {}
+            Type::Array(ty, _)
+            | Type::Reference(ty)
+            | Type::Nullable(ty)
+            | Type::Pointer(ty, _)
+            | Type::FixedBuffer(ty, _) => self.type_origins(ty, generics, res),
+            Type::Tuple(elements) => {
+                for x in elements {
+                    self.type_origins(x.type_(), generics, res);
+                }
+            }
+            Type::FunctionPointer(parameters, ret) => {
+                for ty in parameters {
+                    self.type_origins(ty, generics, res);
+                }
+                self.type_origins(ret, generics, res);
+            }
+            Type::Object(name, arguments) => {
+                if !(arguments.is_empty() && generics.contains(name)) {
+                    res.push(self.origin(name, arguments.len()));
+                }
+                for ty in arguments {
+                    self.type_origins(ty, generics, res);
+                }
+            }
+        }
+    }
+
+    /// Rewrite every type in `unit` to its fully qualified name where it can be
+    /// determined unambiguously, leaving the rest untouched.
+    pub fn resolve(&self, unit: &mut CompilationUnit) {
+        let usings = unit
+            .chunks()
+            .iter()
+            .map(|x| unit.usings_at(x.span()))
+            .collect::<Vec<_>>();
+        for (chunk, usings) in unit.chunks_mut().iter_mut().zip(usings) {
+            self.resolve_chunk(chunk, &usings, &[]);
+        }
+    }
+
+    /// `outer` are the type parameters of the enclosing types, which nested
+    /// types can refer to as well.
+    fn resolve_chunk(&self, chunk: &mut Chunk, usings: &[Using], outer: &[String]) {
+        let scope = chunk.full_name();
+        let generics = in_scope(outer, chunk);
+        for (declared, types) in chunk.scoped_types_mut() {
+            let generics = [generics.as_slice(), &declared].concat();
+            for ty in types {
+                self.resolve_type(ty, &scope, usings, &generics);
+            }
+        }
+        for nested in chunk.nested_mut() {
+            self.resolve_chunk(nested, usings, &generics);
+        }
+    }
+
+    fn resolve_type(&self, ty: &mut Type, scope: &str, usings: &[Using], generics: &[String]) {
+        match ty {
+            Type::Primitive(_) => {}
+            Type::Array(ty, _)
+            | Type::Reference(ty)
+            | Type::Nullable(ty)
+            | Type::Pointer(ty, _)
+            | Type::FixedBuffer(ty, _) => self.resolve_type(ty, scope, usings, generics),
+            Type::Tuple(elements) => {
+                for ty in elements.iter_mut().flat_map(|x| x.types_mut()) {
+                    self.resolve_type(ty, scope, usings, generics);
+                }
+            }
+            Type::FunctionPointer(parameters, ret) => {
+                for ty in parameters {
+                    self.resolve_type(ty, scope, usings, generics);
+                }
+                self.resolve_type(ret, scope, usings, generics);
+            }
+            Type::Object(name, arguments) => {
+                for arg in arguments.iter_mut() {
+                    self.resolve_type(arg, scope, usings, generics);
+                }
+                // Type parameters shadow every other type of the same name.
+                if arguments.is_empty() && generics.contains(name) {
+                    return;
+                }
+                match self.lookup(name, arguments.len(), scope, usings) {
+                    Some(Type::Object(resolved, x)) if x.is_empty() => *name = resolved,
+                    // An alias may stand for a whole constructed type.
+                    Some(resolved) if arguments.is_empty() => *ty = resolved,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn is_known(&self, name: &str, arity: usize) -> bool {
+        self.known.contains(&(name.to_string(), arity))
+    }
+
+    fn lookup(&self, name: &str, arity: usize, scope: &str, usings: &[Using]) -> Option<Type> {
+        if name.contains('.') || name.contains("::") {
+            return None;
+        }
+
+        // Aliases cannot be generic.
+        for using in usings {
+            if let Using::Alias(alias, target) = using
+                && alias == name
+                && arity == 0
+            {
+                return Some(target.clone());
+            }
+        }
+
+        // Nested types and types in enclosing namespaces shadow imported ones.
+        let mut prefix = Some(scope);
+        while let Some(x) = prefix {
+            let candidate = format!("{}.{}", x, name);
+            if self.is_known(&candidate, arity) {
+                return Some(Type::Object(candidate, Vec::new()));
+            }
+            prefix = x.rsplit_once('.').map(|(x, _)| x);
+        }
+        if self.is_known(name, arity) {
+            return None;
+        }
+
+        // `IEnumerable` and `IEnumerable<T>` are told apart by their arity.
+        let mut candidates = usings
+            .iter()
+            .filter_map(|x| match x {
+                Using::Namespace(namespace) => {
+                    let candidate = format!("{}.{}", namespace, name);
+                    (self.is_known(&candidate, arity) || is_framework(namespace, name, arity))
+                        .then_some(candidate)
+                }
+                Using::Static(ty) => {
+                    let candidate = format!("{}.{}", ty, name);
+                    self.is_known(&candidate, arity).then_some(candidate)
+                }
+                Using::Alias(..) => None,
+            })
+            .collect::<Vec<_>>();
+        candidates.dedup();
+
+        match candidates.as_slice() {
+            [candidate] => Some(Type::Object(candidate.clone(), Vec::new())),
+            _ => None,
+        }
+    }
+}
+
+/// Type parameters visible inside `chunk`, its own and those of `outer`.
+fn in_scope(outer: &[String], chunk: &Chunk) -> Vec<String> {
+    let mut res = outer.to_vec();
+    res.extend(chunk.type_parameters().iter().map(|x| x.name().to_string()));
+    res
+}
+
+fn is_framework(namespace: &str, name: &str, arity: usize) -> bool {
+    FRAMEWORK_TYPES
+        .iter()
+        .filter(|(ns, _)| *ns == namespace)
+        .flat_map(|(_, names)| names.iter())
+        .any(|x| match x.split_once('`') {
+            None => arity == 0 && *x == name,
+            Some((x, "*")) => arity > 0 && x == name,
+            Some((x, n)) => x == name && n.parse() == Ok(arity),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decompiler::Fixture;
+    use crate::tests::{find, parse};
+
+    /// Resolved names of the field types of class `name`.
+    fn field_types(units: &[CompilationUnit], name: &str) -> Vec<String> {
+        let Chunk::Class(x) = find(units, name) else {
+            panic!("{} is not a class", name);
+        };
+        x.variables().iter().map(|x| type_name(x.type_())).collect()
+    }
+
+    fn type_name(ty: &Type) -> String {
+        match ty {
+            Type::Object(name, arguments) if arguments.is_empty() => name.clone(),
+            Type::Object(name, arguments) => format!(
+                "{}<{}>",
+                name,
+                arguments
+                    .iter()
+                    .map(type_name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Type::Primitive(x) => format!("{:?}", x),
+            ty => format!("{:?}", ty),
+        }
+    }
+
+    #[test]
+    fn game_types_resolve_through_enclosing_namespaces_and_usings() {
+        let units = parse(
+            &Fixture::new()
+                .with_file("A.cs", "namespace Game { class A {} }")
+                .with_file("B.cs", "namespace Game.UI { class B { A a; C c; } }")
+                .with_file("C.cs", "namespace Other { class C {} }")
+                .with_file(
+                    "D.cs",
+                    "using Other; namespace Game.UI { class D { C c; } }",
+                ),
+        )
+        .unwrap();
+        assert_eq!(field_types(&units, "Game.UI.B"), ["Game.A", "C"]);
+        assert_eq!(field_types(&units, "Game.UI.D"), ["Other.C"]);
+    }
+
+    #[test]
+    fn type_parameters_shadow_game_types() {
+        let units = parse(&Fixture::new().with_file(
+            "A.cs",
+            "namespace G {
+                class Item {}
+                class Box<Item> { Item value; T Get<T>(Item x) { return default; } }
+                class Holder { Item item; }
+            }",
+        ))
+        .unwrap();
+        assert_eq!(field_types(&units, "G.Box"), ["Item"]);
+        assert_eq!(field_types(&units, "G.Holder"), ["G.Item"]);
+
+        let Chunk::Class(x) = find(&units, "G.Box") else {
+            panic!("G.Box is not a class");
+        };
+        let method = &x.methods()[0];
+        assert_eq!(type_name(method.return_type()), "T");
+        assert_eq!(type_name(method.parameters()[0].type_()), "Item");
+    }
+
+    #[test]
+    fn framework_types_are_told_apart_by_arity() {
+        let units = parse(&Fixture::new().with_file(
+            "A.cs",
+            "using System.Collections;
+            using System.Collections.Generic;
+            namespace G { class A { IEnumerable<int> a; IEnumerable b; List<A> c; } }",
+        ))
+        .unwrap();
+        assert_eq!(
+            field_types(&units, "G.A"),
+            [
+                "System.Collections.Generic.IEnumerable<Int>",
+                "System.Collections.IEnumerable",
+                "System.Collections.Generic.List<G.A>",
+            ]
+        );
+    }
+
+    #[test]
+    fn global_qualified_names_are_fully_qualified() {
+        let units = parse(&Fixture::new().with_file(
+            "A.cs",
+            "namespace G { class A { global::System.String a; global::G.A b; } }",
+        ))
+        .unwrap();
+        assert_eq!(field_types(&units, "G.A"), ["System.String", "G.A"]);
+
+        let resolver = Resolver::new(&units);
+        let origins = resolver.origins(find(&units, "G.A"), &|_| true);
+        assert_eq!(origins, [Origin::Framework, Origin::Game]);
+    }
+
+    #[test]
+    fn usings_are_scoped_to_their_namespace_block() {
+        let units = parse(&Fixture::new().with_file(
+            "A.cs",
+            "namespace Other { class C {} }
+            namespace A { using Other; class X { C c; } }
+            namespace B { class Y { C c; } }",
+        ))
+        .unwrap();
+        assert_eq!(field_types(&units, "A.X"), ["Other.C"]);
+        assert_eq!(field_types(&units, "B.Y"), ["C"]);
+    }
+}
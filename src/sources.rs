@@ -0,0 +1,127 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+
+/// Files ilspycmd emits that carry no types worth extracting.
+const GENERATED_FILES: &[&str] = &["AssemblyInfo.cs", "<Module>.cs", "-Module-.cs"];
+
+/// A C# source file found under the input root.
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    /// Path relative to the input root, e.g. `Game/UI/UIGame.cs`.
+    pub relative: PathBuf,
+}
+
+impl SourceFile {
+    /// Namespace implied by the directory layout of an `ilspycmd -p` project.
+    pub fn namespace_hint(&self) -> Option<String> {
+        let parts = self
+            .relative
+            .parent()?
+            .components()
+            .filter_map(|x| x.as_os_str().to_str())
+            .collect::<Vec<_>>();
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join("."))
+        }
+    }
+}
+
+/// Recursively collect the `.cs` files under `root`, sorted by relative path.
+///
+/// A single file is returned as-is, regardless of its name.
+pub fn discover(root: &Path) -> Result<Vec<SourceFile>> {
+    if root.is_file() {
+        return Ok(vec![SourceFile {
+            path: root.to_path_buf(),
+            relative: PathBuf::from(root.file_name().unwrap_or(root.as_os_str())),
+        }]);
+    }
+
+    let mut files = Vec::new();
+    walk(root, root, &mut files)?;
+    files.sort_by(|a, b| a.relative.cmp(&b.relative));
+    Ok(files)
+}
+
+fn walk(root: &Path, dir: &Path, files: &mut Vec<SourceFile>) -> Result<()> {
+    let entries =
+        fs::read_dir(dir).with_context(|| format!("Could not read directory {:?}", dir))?;
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+
+        if name.starts_with('.') {
+            continue;
+        }
+
+        if file_type.is_dir() {
+            if name == "bin" || name == "obj" {
+                continue;
+            }
+            walk(root, &path, files)?;
+        } else if file_type.is_file()
+            && path.extension().is_some_and(|x| x == "cs")
+            && !GENERATED_FILES.contains(&name.as_ref())
+        {
+            let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+            files.push(SourceFile { path, relative });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decompiler::{Decompiler, Fixture};
+    use crate::tests::{find, parse};
+    use crate::workdir::WorkDir;
+
+    #[test]
+    fn discovers_sources_recursively() {
+        let work_dir = WorkDir::create(false).unwrap();
+        Fixture::new()
+            .with_file("B.cs", "")
+            .with_file("Game/UI/A.cs", "")
+            .with_file("Properties/AssemblyInfo.cs", "")
+            .with_file("obj/Debug/Generated.cs", "")
+            .with_file(".hidden/C.cs", "")
+            .with_file("Game/readme.txt", "")
+            .decompile(work_dir.path(), work_dir.path())
+            .unwrap();
+
+        let files = discover(work_dir.path()).unwrap();
+        let relative = files.iter().map(|x| x.relative.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            relative,
+            [PathBuf::from("B.cs"), PathBuf::from("Game/UI/A.cs")]
+        );
+        assert_eq!(files[0].namespace_hint(), None);
+        assert_eq!(files[1].namespace_hint().as_deref(), Some("Game.UI"));
+    }
+
+    #[test]
+    fn files_without_a_namespace_use_their_directory() {
+        let units = parse(
+            &Fixture::new()
+                .with_file("Game/UI/A.cs", "class A {}")
+                .with_file("Game/UI/B.cs", "namespace Other { class B {} }")
+                .with_file("C.cs", "class C {}"),
+        )
+        .unwrap();
+        find(&units, "Game.UI.A");
+        find(&units, "Other.B");
+        find(&units, "C");
+    }
+}
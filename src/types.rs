@@ -1,6 +1,72 @@
-use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone, Default)]
+/// Where a declaration sits in its source file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    start: usize,
+    end: usize,
+    line: usize,
+    column: usize,
+    end_line: usize,
+    end_column: usize,
+}
+
+impl Span {
+    pub fn new(
+        start: usize,
+        end: usize,
+        line: usize,
+        column: usize,
+        end_line: usize,
+        end_column: usize,
+    ) -> Self {
+        Span {
+            start,
+            end,
+            line,
+            column,
+            end_line,
+            end_column,
+        }
+    }
+
+    /// Byte offset of the first character.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Byte offset just past the last character.
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// One-based line of the first character.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// One-based column of the first character, in bytes.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// One-based line just past the last character.
+    pub fn end_line(&self) -> usize {
+        self.end_line
+    }
+
+    /// One-based column just past the last character, in bytes.
+    pub fn end_column(&self) -> usize {
+        self.end_column
+    }
+
+    /// Whether `other` lies within this span.
+    pub fn contains(&self, other: Span) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub enum Primitive {
     #[default]
     Void,
@@ -18,27 +84,39 @@ pub enum Primitive {
     Sbyte,
     Char,
     Object,
+    Decimal,
+    /// `nint`, pointer sized.
+    Nint,
+    /// `nuint`, pointer sized.
+    Nuint,
+    Dynamic,
 }
 
-impl From<&str> for Primitive {
-    fn from(s: &str) -> Self {
+impl TryFrom<&str> for Primitive {
+    type Error = String;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
         match s {
-            "void" => Primitive::Void,
-            "bool" => Primitive::Bool,
-            "short" => Primitive::Short,
-            "ushort" => Primitive::Ushort,
-            "int" => Primitive::Int,
-            "uint" => Primitive::Uint,
-            "long" => Primitive::Long,
-            "ulong" => Primitive::Ulong,
-            "float" => Primitive::Float,
-            "double" => Primitive::Double,
-            "string" => Primitive::String,
-            "byte" => Primitive::Byte,
-            "sbyte" => Primitive::Sbyte,
-            "char" => Primitive::Char,
-            "object" => Primitive::Object,
-            _ => panic!("Invalid primitive {}", s),
+            "void" => Ok(Primitive::Void),
+            "bool" => Ok(Primitive::Bool),
+            "short" => Ok(Primitive::Short),
+            "ushort" => Ok(Primitive::Ushort),
+            "int" => Ok(Primitive::Int),
+            "uint" => Ok(Primitive::Uint),
+            "long" => Ok(Primitive::Long),
+            "ulong" => Ok(Primitive::Ulong),
+            "float" => Ok(Primitive::Float),
+            "double" => Ok(Primitive::Double),
+            "string" => Ok(Primitive::String),
+            "byte" => Ok(Primitive::Byte),
+            "sbyte" => Ok(Primitive::Sbyte),
+            "char" => Ok(Primitive::Char),
+            "object" => Ok(Primitive::Object),
+            "decimal" => Ok(Primitive::Decimal),
+            "nint" => Ok(Primitive::Nint),
+            "nuint" => Ok(Primitive::Nuint),
+            "dynamic" => Ok(Primitive::Dynamic),
+            _ => Err(format!("Invalid primitive {}", s)),
         }
     }
 }
@@ -46,10 +124,20 @@ impl From<&str> for Primitive {
 #[derive(Debug, Clone)]
 pub enum Type {
     Primitive(Primitive),
+    /// Element type and rank, `int[,]` has rank 2. Jagged arrays nest, so
+    /// `int[][,]` is a rank 1 array of `int[,]`.
     Array(Box<Type>, u8),
-    Tuple(Vec<Type>),
+    Tuple(Vec<TupleElement>),
     Reference(Box<Type>),
-    Object(String, Option<Box<Type>>),
+    Nullable(Box<Type>),
+    /// Pointee type and indirection depth, `int**` has depth 2.
+    Pointer(Box<Type>, u8),
+    /// Parameter types and return type of a `delegate*`.
+    FunctionPointer(Vec<Type>, Box<Type>),
+    /// Element type and length of a `fixed` buffer, `fixed byte data[16]`.
+    FixedBuffer(Box<Type>, usize),
+    /// A named type and its generic arguments, if any.
+    Object(String, Vec<Type>),
 }
 
 impl Default for Type {
@@ -59,6 +147,34 @@ impl Default for Type {
 }
 
 #[derive(Debug, Clone, Default)]
+pub struct TupleElement {
+    name: Option<String>,
+    type_: Type,
+}
+
+impl TupleElement {
+    pub fn new(name: Option<String>, type_: Type) -> Self {
+        TupleElement { name, type_ }
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn type_(&self) -> &Type {
+        &self.type_
+    }
+
+    pub fn types(&self) -> Vec<&Type> {
+        vec![&self.type_]
+    }
+
+    pub fn types_mut(&mut self) -> Vec<&mut Type> {
+        vec![&mut self.type_]
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub enum Modifier {
     #[default]
     Public,
@@ -67,7 +183,6 @@ pub enum Modifier {
     Static,
     Const,
     Override,
-    Final,
     Abstract,
     Internal,
     Sealed,
@@ -76,184 +191,1622 @@ pub enum Modifier {
     New,
     Unsafe,
     Extern,
+    Fixed,
+    Volatile,
+    Async,
+    Required,
+    Partial,
+}
+
+impl TryFrom<&str> for Modifier {
+    type Error = String;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "public" => Ok(Modifier::Public),
+            "private" => Ok(Modifier::Private),
+            "protected" => Ok(Modifier::Protected),
+            "static" => Ok(Modifier::Static),
+            "const" => Ok(Modifier::Const),
+            "override" => Ok(Modifier::Override),
+            "abstract" => Ok(Modifier::Abstract),
+            "internal" => Ok(Modifier::Internal),
+            "sealed" => Ok(Modifier::Sealed),
+            "virtual" => Ok(Modifier::Virtual),
+            "readonly" => Ok(Modifier::Readonly),
+            "new" => Ok(Modifier::New),
+            "unsafe" => Ok(Modifier::Unsafe),
+            "extern" => Ok(Modifier::Extern),
+            "fixed" => Ok(Modifier::Fixed),
+            "volatile" => Ok(Modifier::Volatile),
+            "async" => Ok(Modifier::Async),
+            "required" => Ok(Modifier::Required),
+            "partial" => Ok(Modifier::Partial),
+            _ => Err(format!("Invalid modifier {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Variance {
+    #[default]
+    Invariant,
+    /// `in T`, contravariant
+    In,
+    /// `out T`, covariant
+    Out,
+}
+
+#[derive(Debug, Clone)]
+pub enum Constraint {
+    Class,
+    /// `class?`
+    NullableClass,
+    Struct,
+    Unmanaged,
+    NotNull,
+    Default,
+    /// `new()`
+    New,
+    Type(Type),
 }
 
-impl From<&str> for Modifier {
-    fn from(s: &str) -> Self {
+impl TryFrom<&str> for Constraint {
+    type Error = String;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
         match s {
-            "public" => Modifier::Public,
-            "private" => Modifier::Private,
-            "protected" => Modifier::Protected,
-            "static" => Modifier::Static,
-            "const" => Modifier::Const,
-            "override" => Modifier::Override,
-            "final" => Modifier::Final,
-            "abstract" => Modifier::Abstract,
-            "internal" => Modifier::Internal,
-            "sealed" => Modifier::Sealed,
-            "virtual" => Modifier::Virtual,
-            "readonly" => Modifier::Readonly,
-            "new" => Modifier::New,
-            "unsafe" => Modifier::Unsafe,
-            "extern" => Modifier::Extern,
-            _ => panic!("Invalid modifier {}", s),
+            "class" => Ok(Constraint::Class),
+            "class?" => Ok(Constraint::NullableClass),
+            "struct" => Ok(Constraint::Struct),
+            "unmanaged" => Ok(Constraint::Unmanaged),
+            "notnull" => Ok(Constraint::NotNull),
+            "default" => Ok(Constraint::Default),
+            "new()" => Ok(Constraint::New),
+            _ => Err(format!("Invalid constraint {}", s)),
         }
     }
 }
 
 #[derive(Debug, Clone, Default)]
-pub struct Variable {
+pub struct TypeParameter {
     name: String,
-    modifiers: Vec<Modifier>,
-    type_: Type,
-    value: Option<String>,
+    variance: Variance,
+    constraints: Vec<Constraint>,
 }
 
-impl Variable {
-    pub fn new(name: String, modifiers: Vec<Modifier>, type_: Type, value: Option<String>) -> Self {
-        Variable {
+fn names(type_parameters: &[TypeParameter]) -> Vec<String> {
+    type_parameters.iter().map(|x| x.name.clone()).collect()
+}
+
+impl TypeParameter {
+    pub fn new(name: String, variance: Variance, constraints: Vec<Constraint>) -> Self {
+        TypeParameter {
             name,
-            modifiers,
-            type_,
-            value,
+            variance,
+            constraints,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn variance(&self) -> Variance {
+        self.variance
+    }
+
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
+    pub fn types(&self) -> Vec<&Type> {
+        self.constraints
+            .iter()
+            .filter_map(|x| match x {
+                Constraint::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn types_mut(&mut self) -> Vec<&mut Type> {
+        self.constraints
+            .iter_mut()
+            .filter_map(|x| match x {
+                Constraint::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Type of a real literal, by its suffix.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RealKind {
+    /// `f` or `F`
+    Float,
+    /// `d`, `D` or no suffix
+    #[default]
+    Double,
+    /// `m` or `M`
+    Decimal,
+}
+
+/// A constant as written in the source, e.g. an attribute argument.
+#[derive(Debug, Clone)]
+pub enum Literal {
+    Null,
+    Bool(bool),
+    Integer(i128),
+    Real(f64, RealKind),
+    Char(char),
+    String(String),
+    TypeOf(Type),
+    /// Anything else, kept as written, e.g. `LayoutKind.Explicit`.
+    Expression(String),
+}
+
+/// An expression tree, detailed enough to read initializers and constants.
+#[derive(Debug, Clone)]
+pub enum Expression {
+    Literal(Literal),
+    /// A simple name, `SomeConst`.
+    Name(String),
+    /// `Color.red`
+    MemberAccess(Box<Expression>, String),
+    /// Prefix operator and operand, `~x`.
+    Unary(String, Box<Expression>),
+    /// Left operand, operator and right operand, `1 << 4`.
+    Binary(Box<Expression>, String, Box<Expression>),
+    /// `a ? b : c`
+    Conditional(Box<Expression>, Box<Expression>, Box<Expression>),
+    Cast(Type, Box<Expression>),
+    /// Function and arguments, `Foo.Bar(1)`.
+    Invocation(Box<Expression>, Vec<Expression>),
+    /// Type, arguments and initializer of `new T(..) { .. }`. The type is
+    /// `None` for a target-typed `new(..)`.
+    New(Option<Type>, Vec<Expression>, Vec<Expression>),
+    /// Array type and elements of `new T[] { .. }`, `new[] { .. }` or a bare
+    /// `{ .. }` initializer.
+    Array(Option<Type>, Vec<Expression>),
+    Default(Option<Type>),
+    SizeOf(Type),
+    /// Anything else, kept as written, e.g. lambdas.
+    Other(String),
+}
+
+impl Expression {
+    pub fn types(&self) -> Vec<&Type> {
+        match self {
+            Expression::Literal(Literal::TypeOf(ty)) | Expression::SizeOf(ty) => vec![ty],
+            Expression::Literal(_) | Expression::Name(_) | Expression::Other(_) => Vec::new(),
+            Expression::MemberAccess(x, _) | Expression::Unary(_, x) => x.types(),
+            Expression::Binary(x, _, y) => {
+                let mut res = x.types();
+                res.extend(y.types());
+                res
+            }
+            Expression::Conditional(x, y, z) => {
+                let mut res = x.types();
+                res.extend(y.types());
+                res.extend(z.types());
+                res
+            }
+            Expression::Cast(ty, x) => {
+                let mut res = vec![ty];
+                res.extend(x.types());
+                res
+            }
+            Expression::Invocation(x, args) => {
+                let mut res = x.types();
+                res.extend(args.iter().flat_map(|x| x.types()));
+                res
+            }
+            Expression::New(ty, args, initializer) => {
+                let mut res = ty.iter().collect::<Vec<_>>();
+                res.extend(args.iter().flat_map(|x| x.types()));
+                res.extend(initializer.iter().flat_map(|x| x.types()));
+                res
+            }
+            Expression::Array(ty, elements) => {
+                let mut res = ty.iter().collect::<Vec<_>>();
+                res.extend(elements.iter().flat_map(|x| x.types()));
+                res
+            }
+            Expression::Default(ty) => ty.iter().collect(),
+        }
+    }
+
+    pub fn types_mut(&mut self) -> Vec<&mut Type> {
+        match self {
+            Expression::Literal(Literal::TypeOf(ty)) | Expression::SizeOf(ty) => vec![ty],
+            Expression::Literal(_) | Expression::Name(_) | Expression::Other(_) => Vec::new(),
+            Expression::MemberAccess(x, _) | Expression::Unary(_, x) => x.types_mut(),
+            Expression::Binary(x, _, y) => {
+                let mut res = x.types_mut();
+                res.extend(y.types_mut());
+                res
+            }
+            Expression::Conditional(x, y, z) => {
+                let mut res = x.types_mut();
+                res.extend(y.types_mut());
+                res.extend(z.types_mut());
+                res
+            }
+            Expression::Cast(ty, x) => {
+                let mut res = vec![ty];
+                res.extend(x.types_mut());
+                res
+            }
+            Expression::Invocation(x, args) => {
+                let mut res = x.types_mut();
+                res.extend(args.iter_mut().flat_map(|x| x.types_mut()));
+                res
+            }
+            Expression::New(ty, args, initializer) => {
+                let mut res = ty.iter_mut().collect::<Vec<_>>();
+                res.extend(args.iter_mut().flat_map(|x| x.types_mut()));
+                res.extend(initializer.iter_mut().flat_map(|x| x.types_mut()));
+                res
+            }
+            Expression::Array(ty, elements) => {
+                let mut res = ty.iter_mut().collect::<Vec<_>>();
+                res.extend(elements.iter_mut().flat_map(|x| x.types_mut()));
+                res
+            }
+            Expression::Default(ty) => ty.iter_mut().collect(),
         }
     }
 }
 
+/// An attribute such as `[StructLayout(LayoutKind.Explicit, Size = 16)]`.
 #[derive(Debug, Clone, Default)]
-pub struct Method {
+pub struct Attribute {
     name: String,
-    modifiers: Vec<Modifier>,
-    return_type: Type,
-    parameters: HashMap<String, Type>,
-    body: String,
+    /// Explicit target, `field` in `[field: NonSerialized]`.
+    target: Option<String>,
+    arguments: Vec<Literal>,
+    /// Property assignments `Size = 16` and named arguments `size: 16`.
+    named_arguments: Vec<(String, Literal)>,
 }
 
-impl Method {
+impl Attribute {
     pub fn new(
         name: String,
-        modifiers: Vec<Modifier>,
-        return_type: Type,
-        parameters: HashMap<String, Type>,
-        body: String,
+        target: Option<String>,
+        arguments: Vec<Literal>,
+        named_arguments: Vec<(String, Literal)>,
     ) -> Self {
-        Method {
+        Attribute {
             name,
-            modifiers,
-            return_type,
-            parameters,
-            body,
+            target,
+            arguments,
+            named_arguments,
         }
     }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+
+    pub fn arguments(&self) -> &[Literal] {
+        &self.arguments
+    }
+
+    pub fn named_arguments(&self) -> &[(String, Literal)] {
+        &self.named_arguments
+    }
+
+    /// Named argument `name`, whether assigned or passed by name.
+    pub fn named_argument(&self, name: &str) -> Option<&Literal> {
+        self.named_arguments
+            .iter()
+            .find(|(x, _)| x == name)
+            .map(|(_, x)| x)
+    }
 }
 
 #[derive(Debug, Clone, Default)]
-pub struct Class {
+pub struct Variable {
     name: String,
     modifiers: Vec<Modifier>,
-    base_class: Vec<Type>,
-    variables: Vec<Variable>,
-    methods: Vec<Method>,
+    attributes: Vec<Attribute>,
+    type_: Type,
+    value: Option<Expression>,
+    span: Span,
 }
 
-impl Class {
-    pub fn new(name: String, modifiers: Vec<Modifier>, base_class: Vec<Type>) -> Self {
-        Class {
+impl Variable {
+    pub fn new(
+        name: String,
+        modifiers: Vec<Modifier>,
+        type_: Type,
+        value: Option<Expression>,
+    ) -> Self {
+        Variable {
             name,
             modifiers,
-            base_class,
-            ..Default::default()
+            attributes: Vec::new(),
+            type_,
+            value,
+            span: Span::default(),
         }
     }
 
-    pub fn add_variable(&mut self, variable: Variable) {
-        self.variables.push(variable);
+    pub fn span(&self) -> Span {
+        self.span
     }
 
-    pub fn add_method(&mut self, method: Method) {
-        self.methods.push(method);
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn attributes(&self) -> &[Attribute] {
+        &self.attributes
+    }
+
+    pub fn set_attributes(&mut self, attributes: Vec<Attribute>) {
+        self.attributes = attributes;
+    }
+
+    pub fn modifiers(&self) -> &[Modifier] {
+        &self.modifiers
+    }
+
+    pub fn type_(&self) -> &Type {
+        &self.type_
+    }
+
+    /// Initializer, `5` in `int x = 5`.
+    pub fn value(&self) -> Option<&Expression> {
+        self.value.as_ref()
+    }
+
+    pub fn types(&self) -> Vec<&Type> {
+        let mut res = vec![&self.type_];
+        if let Some(value) = &self.value {
+            res.extend(value.types());
+        }
+        res
+    }
+
+    pub fn types_mut(&mut self) -> Vec<&mut Type> {
+        let mut res = vec![&mut self.type_];
+        if let Some(value) = &mut self.value {
+            res.extend(value.types_mut());
+        }
+        res
     }
 }
 
-#[derive(Debug, Clone, Default)]
-pub struct Enum {
-    name: String,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessorKind {
+    Get,
+    Set,
+    Init,
+}
+
+impl TryFrom<&str> for AccessorKind {
+    type Error = String;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "get" => Ok(AccessorKind::Get),
+            "set" => Ok(AccessorKind::Set),
+            "init" => Ok(AccessorKind::Init),
+            _ => Err(format!("Invalid accessor {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Accessor {
+    kind: AccessorKind,
     modifiers: Vec<Modifier>,
-    base: Vec<Type>,
-    values: HashMap<String, Option<i32>>,
+    /// Block or `=> expression` source, `None` for an auto-implemented accessor.
+    body: Option<String>,
 }
 
-impl Enum {
-    pub fn new(name: String, modifiers: Vec<Modifier>, base: Vec<Type>) -> Self {
-        Self {
-            name,
+impl Accessor {
+    pub fn new(kind: AccessorKind, modifiers: Vec<Modifier>, body: Option<String>) -> Self {
+        Accessor {
+            kind,
             modifiers,
-            base,
-            ..Default::default()
+            body,
         }
     }
 
-    pub fn add_value(&mut self, name: String, value: Option<i32>) {
-        self.values.insert(name, value);
+    pub fn kind(&self) -> AccessorKind {
+        self.kind
+    }
+
+    pub fn modifiers(&self) -> &[Modifier] {
+        &self.modifiers
+    }
+
+    pub fn body(&self) -> Option<&str> {
+        self.body.as_deref()
     }
 }
 
 #[derive(Debug, Clone, Default)]
-pub struct Struct {
+pub struct Property {
     name: String,
     modifiers: Vec<Modifier>,
-    base: Vec<Type>,
-    variables: Vec<Variable>,
-    methods: Vec<Method>,
+    type_: Type,
+    accessors: Vec<Accessor>,
+    /// Expression of an expression-bodied property, `int X => expression;`.
+    expression: Option<String>,
+    /// Initializer of an auto-property, `int X { get; } = value;`.
+    initializer: Option<String>,
+    span: Span,
 }
 
-impl Struct {
-    pub fn new(name: String, modifiers: Vec<Modifier>, base: Vec<Type>) -> Self {
-        Self {
+impl Property {
+    pub fn new(
+        name: String,
+        modifiers: Vec<Modifier>,
+        type_: Type,
+        accessors: Vec<Accessor>,
+        expression: Option<String>,
+        initializer: Option<String>,
+    ) -> Self {
+        Property {
             name,
             modifiers,
-            base,
-            ..Default::default()
+            type_,
+            accessors,
+            expression,
+            initializer,
+            span: Span::default(),
         }
     }
 
-    pub fn add_variable(&mut self, variable: Variable) {
-        self.variables.push(variable);
+    pub fn span(&self) -> Span {
+        self.span
     }
 
-    pub fn add_method(&mut self, method: Method) {
-        self.methods.push(method);
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn modifiers(&self) -> &[Modifier] {
+        &self.modifiers
+    }
+
+    pub fn type_(&self) -> &Type {
+        &self.type_
+    }
+
+    pub fn accessors(&self) -> &[Accessor] {
+        &self.accessors
+    }
+
+    pub fn accessor(&self, kind: AccessorKind) -> Option<&Accessor> {
+        self.accessors.iter().find(|x| x.kind == kind)
+    }
+
+    pub fn expression(&self) -> Option<&str> {
+        self.expression.as_deref()
+    }
+
+    pub fn initializer(&self) -> Option<&str> {
+        self.initializer.as_deref()
+    }
+
+    /// Whether the compiler generates a backing field, `{ get; set; }`.
+    pub fn is_auto(&self) -> bool {
+        self.expression.is_none()
+            && !self.accessors.is_empty()
+            && self.accessors.iter().all(|x| x.body.is_none())
+    }
+
+    pub fn types(&self) -> Vec<&Type> {
+        vec![&self.type_]
+    }
+
+    pub fn types_mut(&mut self) -> Vec<&mut Type> {
+        vec![&mut self.type_]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterModifier {
+    Ref,
+    Out,
+    In,
+    Params,
+    This,
+    Scoped,
+    Readonly,
+}
+
+impl TryFrom<&str> for ParameterModifier {
+    type Error = String;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "ref" => Ok(ParameterModifier::Ref),
+            "out" => Ok(ParameterModifier::Out),
+            "in" => Ok(ParameterModifier::In),
+            "params" => Ok(ParameterModifier::Params),
+            "this" => Ok(ParameterModifier::This),
+            "scoped" => Ok(ParameterModifier::Scoped),
+            "readonly" => Ok(ParameterModifier::Readonly),
+            _ => Err(format!("Invalid parameter modifier {}", s)),
+        }
     }
 }
 
 #[derive(Debug, Clone, Default)]
-pub struct Interface {
+pub struct Parameter {
     name: String,
-    modifiers: Vec<Modifier>,
-    base: Vec<Type>,
-    methods: Vec<Method>,
+    type_: Type,
+    modifiers: Vec<ParameterModifier>,
+    /// Default value expression, `int x = 5`.
+    default: Option<Expression>,
+    attributes: Vec<Attribute>,
 }
 
-impl Interface {
-    pub fn new(name: String, modifiers: Vec<Modifier>, base: Vec<Type>) -> Self {
-        Self {
+impl Parameter {
+    pub fn new(
+        name: String,
+        type_: Type,
+        modifiers: Vec<ParameterModifier>,
+        default: Option<Expression>,
+        attributes: Vec<Attribute>,
+    ) -> Self {
+        Parameter {
             name,
+            type_,
             modifiers,
-            base,
-            ..Default::default()
+            default,
+            attributes,
         }
     }
 
-    pub fn add_method(&mut self, method: Method) {
-        self.methods.push(method);
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn type_(&self) -> &Type {
+        &self.type_
+    }
+
+    pub fn modifiers(&self) -> &[ParameterModifier] {
+        &self.modifiers
+    }
+
+    pub fn has_modifier(&self, modifier: ParameterModifier) -> bool {
+        self.modifiers.contains(&modifier)
+    }
+
+    pub fn default(&self) -> Option<&Expression> {
+        self.default.as_ref()
+    }
+
+    pub fn attributes(&self) -> &[Attribute] {
+        &self.attributes
+    }
+
+    pub fn types(&self) -> Vec<&Type> {
+        let mut res = vec![&self.type_];
+        if let Some(default) = &self.default {
+            res.extend(default.types());
+        }
+        res
+    }
+
+    pub fn types_mut(&mut self) -> Vec<&mut Type> {
+        let mut res = vec![&mut self.type_];
+        if let Some(default) = &mut self.default {
+            res.extend(default.types_mut());
+        }
+        res
     }
 }
 
-#[derive(Debug, Clone)]
-pub enum Chunk {
+#[derive(Debug, Clone, Default)]
+pub struct Method {
+    name: String,
+    namespace: Option<String>,
+    modifiers: Vec<Modifier>,
+    attributes: Vec<Attribute>,
+    type_parameters: Vec<TypeParameter>,
+    return_type: Type,
+    parameters: Vec<Parameter>,
+    body: String,
+    span: Span,
+}
+
+impl Method {
+    pub fn new(
+        name: String,
+        modifiers: Vec<Modifier>,
+        return_type: Type,
+        parameters: Vec<Parameter>,
+        body: String,
+    ) -> Self {
+        Method {
+            name,
+            namespace: None,
+            modifiers,
+            attributes: Vec::new(),
+            type_parameters: Vec::new(),
+            return_type,
+            parameters,
+            body,
+            span: Span::default(),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn attributes(&self) -> &[Attribute] {
+        &self.attributes
+    }
+
+    pub fn set_attributes(&mut self, attributes: Vec<Attribute>) {
+        self.attributes = attributes;
+    }
+
+    pub fn type_parameters(&self) -> &[TypeParameter] {
+        &self.type_parameters
+    }
+
+    pub fn set_type_parameters(&mut self, type_parameters: Vec<TypeParameter>) {
+        self.type_parameters = type_parameters;
+    }
+
+    /// Namespace a delegate is declared in, `None` for the global namespace.
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    pub fn set_namespace(&mut self, namespace: Option<String>) {
+        self.namespace = namespace;
+    }
+
+    pub fn modifiers(&self) -> &[Modifier] {
+        &self.modifiers
+    }
+
+    pub fn return_type(&self) -> &Type {
+        &self.return_type
+    }
+
+    pub fn parameters(&self) -> &[Parameter] {
+        &self.parameters
+    }
+
+    /// Types of the method along with its own type parameters.
+    pub fn scoped_types(&self) -> (Vec<String>, Vec<&Type>) {
+        (names(&self.type_parameters), self.types())
+    }
+
+    /// Same as `scoped_types`, for rewriting the types in place.
+    pub fn scoped_types_mut(&mut self) -> (Vec<String>, Vec<&mut Type>) {
+        (names(&self.type_parameters), self.types_mut())
+    }
+
+    pub fn types(&self) -> Vec<&Type> {
+        let mut res = vec![&self.return_type];
+        res.extend(self.type_parameters.iter().flat_map(|x| x.types()));
+        res.extend(self.parameters.iter().flat_map(|x| x.types()));
+        res
+    }
+
+    pub fn types_mut(&mut self) -> Vec<&mut Type> {
+        let mut res = vec![&mut self.return_type];
+        res.extend(self.type_parameters.iter_mut().flat_map(|x| x.types_mut()));
+        res.extend(self.parameters.iter_mut().flat_map(|x| x.types_mut()));
+        res
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ConstructorInitializer {
+    /// `: base(arguments)`
+    Base(Vec<String>),
+    /// `: this(arguments)`
+    This(Vec<String>),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Constructor {
+    modifiers: Vec<Modifier>,
+    parameters: Vec<Parameter>,
+    initializer: Option<ConstructorInitializer>,
+    body: String,
+    span: Span,
+}
+
+impl Constructor {
+    pub fn new(
+        modifiers: Vec<Modifier>,
+        parameters: Vec<Parameter>,
+        initializer: Option<ConstructorInitializer>,
+        body: String,
+    ) -> Self {
+        Constructor {
+            modifiers,
+            parameters,
+            initializer,
+            body,
+            span: Span::default(),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn modifiers(&self) -> &[Modifier] {
+        &self.modifiers
+    }
+
+    pub fn parameters(&self) -> &[Parameter] {
+        &self.parameters
+    }
+
+    pub fn initializer(&self) -> Option<&ConstructorInitializer> {
+        self.initializer.as_ref()
+    }
+
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.modifiers.contains(&Modifier::Static)
+    }
+
+    pub fn types(&self) -> Vec<&Type> {
+        self.parameters.iter().flat_map(|x| x.types()).collect()
+    }
+
+    pub fn types_mut(&mut self) -> Vec<&mut Type> {
+        self.parameters
+            .iter_mut()
+            .flat_map(|x| x.types_mut())
+            .collect()
+    }
+}
+
+/// A destructor, `~Name() { ... }`.
+#[derive(Debug, Clone, Default)]
+pub struct Finalizer {
+    body: String,
+    span: Span,
+}
+
+impl Finalizer {
+    pub fn new(body: String) -> Self {
+        Finalizer {
+            body,
+            span: Span::default(),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Class {
+    name: String,
+    namespace: Option<String>,
+    modifiers: Vec<Modifier>,
+    attributes: Vec<Attribute>,
+    type_parameters: Vec<TypeParameter>,
+    base_class: Vec<Type>,
+    variables: Vec<Variable>,
+    properties: Vec<Property>,
+    constructors: Vec<Constructor>,
+    static_constructor: Option<Constructor>,
+    finalizer: Option<Finalizer>,
+    methods: Vec<Method>,
+    nested: Vec<Chunk>,
+    span: Span,
+}
+
+impl Class {
+    pub fn new(name: String, modifiers: Vec<Modifier>, base_class: Vec<Type>) -> Self {
+        Class {
+            name,
+            modifiers,
+            base_class,
+            ..Default::default()
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn add_variables(&mut self, variables: Vec<Variable>) {
+        self.variables.extend(variables);
+    }
+
+    pub fn add_property(&mut self, property: Property) {
+        self.properties.push(property);
+    }
+
+    /// Static constructors are kept apart from the instance ones.
+    pub fn add_constructor(&mut self, constructor: Constructor) {
+        if constructor.is_static() {
+            self.static_constructor = Some(constructor);
+        } else {
+            self.constructors.push(constructor);
+        }
+    }
+
+    pub fn set_finalizer(&mut self, finalizer: Finalizer) {
+        self.finalizer = Some(finalizer);
+    }
+
+    pub fn add_method(&mut self, method: Method) {
+        self.methods.push(method);
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn attributes(&self) -> &[Attribute] {
+        &self.attributes
+    }
+
+    pub fn set_attributes(&mut self, attributes: Vec<Attribute>) {
+        self.attributes = attributes;
+    }
+
+    pub fn type_parameters(&self) -> &[TypeParameter] {
+        &self.type_parameters
+    }
+
+    pub fn set_type_parameters(&mut self, type_parameters: Vec<TypeParameter>) {
+        self.type_parameters = type_parameters;
+    }
+
+    /// Namespace the type is declared in, `None` for the global namespace.
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    pub fn set_namespace(&mut self, namespace: Option<String>) {
+        for chunk in &mut self.nested {
+            chunk.set_namespace(namespace.clone());
+        }
+        self.namespace = namespace;
+    }
+
+    pub fn variables(&self) -> &[Variable] {
+        &self.variables
+    }
+
+    pub fn properties(&self) -> &[Property] {
+        &self.properties
+    }
+
+    pub fn constructors(&self) -> &[Constructor] {
+        &self.constructors
+    }
+
+    pub fn static_constructor(&self) -> Option<&Constructor> {
+        self.static_constructor.as_ref()
+    }
+
+    pub fn finalizer(&self) -> Option<&Finalizer> {
+        self.finalizer.as_ref()
+    }
+
+    pub fn methods(&self) -> &[Method] {
+        &self.methods
+    }
+
+    pub fn add_nested(&mut self, chunk: Chunk) {
+        self.nested.push(chunk);
+    }
+
+    pub fn nested(&self) -> &[Chunk] {
+        &self.nested
+    }
+
+    pub fn nested_mut(&mut self) -> &mut [Chunk] {
+        &mut self.nested
+    }
+
+    /// Every member in declaration order.
+    pub fn members(&self) -> Vec<Member<'_>> {
+        let mut res = Vec::new();
+        res.extend(self.variables.iter().map(Member::Variable));
+        res.extend(self.properties.iter().map(Member::Property));
+        res.extend(self.constructors.iter().map(Member::Constructor));
+        res.extend(self.static_constructor.iter().map(Member::Constructor));
+        res.extend(self.finalizer.iter().map(Member::Finalizer));
+        res.extend(self.methods.iter().map(Member::Method));
+        res.extend(self.nested.iter().map(Member::Nested));
+        res.sort_by_key(|x| x.span().start());
+        res
+    }
+
+    /// Every type referenced by the class itself, excluding nested chunks,
+    /// grouped with the type parameters it may refer to. The class's own come
+    /// first, followed by one group per method.
+    pub fn scoped_types(&self) -> Vec<(Vec<String>, Vec<&Type>)> {
+        let generics = names(&self.type_parameters);
+        let mut res = self.base_class.iter().collect::<Vec<_>>();
+        res.extend(self.type_parameters.iter().flat_map(|x| x.types()));
+        res.extend(self.variables.iter().flat_map(|x| x.types()));
+        res.extend(self.properties.iter().flat_map(|x| x.types()));
+        res.extend(
+            self.constructors
+                .iter()
+                .chain(self.static_constructor.as_ref())
+                .flat_map(|x| x.types()),
+        );
+        let mut groups = vec![(generics, res)];
+        groups.extend(self.methods.iter().map(|x| x.scoped_types()));
+        groups
+    }
+
+    /// Same as `scoped_types`, for rewriting the types in place.
+    pub fn scoped_types_mut(&mut self) -> Vec<(Vec<String>, Vec<&mut Type>)> {
+        let generics = names(&self.type_parameters);
+        let mut res = self.base_class.iter_mut().collect::<Vec<_>>();
+        res.extend(self.type_parameters.iter_mut().flat_map(|x| x.types_mut()));
+        res.extend(self.variables.iter_mut().flat_map(|x| x.types_mut()));
+        res.extend(self.properties.iter_mut().flat_map(|x| x.types_mut()));
+        res.extend(
+            self.constructors
+                .iter_mut()
+                .chain(self.static_constructor.as_mut())
+                .flat_map(|x| x.types_mut()),
+        );
+        let mut groups = vec![(generics, res)];
+        groups.extend(self.methods.iter_mut().map(|x| x.scoped_types_mut()));
+        groups
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EnumMember {
+    name: String,
+    /// `None` when the value depends on constants declared elsewhere.
+    value: Option<i128>,
+    span: Span,
+}
+
+impl EnumMember {
+    pub fn new(name: String, value: Option<i128>, span: Span) -> Self {
+        EnumMember { name, value, span }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> Option<i128> {
+        self.value
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Enum {
+    name: String,
+    namespace: Option<String>,
+    modifiers: Vec<Modifier>,
+    attributes: Vec<Attribute>,
+    underlying: Primitive,
+    members: Vec<EnumMember>,
+    span: Span,
+}
+
+impl Enum {
+    pub fn new(name: String, modifiers: Vec<Modifier>, underlying: Primitive) -> Self {
+        Self {
+            name,
+            modifiers,
+            underlying,
+            ..Default::default()
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn add_member(&mut self, member: EnumMember) {
+        self.members.push(member);
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn attributes(&self) -> &[Attribute] {
+        &self.attributes
+    }
+
+    pub fn set_attributes(&mut self, attributes: Vec<Attribute>) {
+        self.attributes = attributes;
+    }
+
+    /// Namespace the type is declared in, `None` for the global namespace.
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    pub fn set_namespace(&mut self, namespace: Option<String>) {
+        self.namespace = namespace;
+    }
+
+    /// Integral type the values are stored as, `int` unless specified.
+    pub fn underlying(&self) -> &Primitive {
+        &self.underlying
+    }
+
+    /// Members in declaration order.
+    pub fn members(&self) -> &[EnumMember] {
+        &self.members
+    }
+
+    pub fn member(&self, name: &str) -> Option<&EnumMember> {
+        self.members.iter().find(|x| x.name == name)
+    }
+
+    pub fn types(&self) -> Vec<&Type> {
+        Vec::new()
+    }
+
+    pub fn types_mut(&mut self) -> Vec<&mut Type> {
+        Vec::new()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Struct {
+    name: String,
+    namespace: Option<String>,
+    modifiers: Vec<Modifier>,
+    attributes: Vec<Attribute>,
+    type_parameters: Vec<TypeParameter>,
+    base: Vec<Type>,
+    variables: Vec<Variable>,
+    properties: Vec<Property>,
+    constructors: Vec<Constructor>,
+    static_constructor: Option<Constructor>,
+    methods: Vec<Method>,
+    nested: Vec<Chunk>,
+    span: Span,
+}
+
+impl Struct {
+    pub fn new(name: String, modifiers: Vec<Modifier>, base: Vec<Type>) -> Self {
+        Self {
+            name,
+            modifiers,
+            base,
+            ..Default::default()
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn add_variables(&mut self, variables: Vec<Variable>) {
+        self.variables.extend(variables);
+    }
+
+    pub fn add_property(&mut self, property: Property) {
+        self.properties.push(property);
+    }
+
+    /// Static constructors are kept apart from the instance ones.
+    pub fn add_constructor(&mut self, constructor: Constructor) {
+        if constructor.is_static() {
+            self.static_constructor = Some(constructor);
+        } else {
+            self.constructors.push(constructor);
+        }
+    }
+
+    pub fn add_method(&mut self, method: Method) {
+        self.methods.push(method);
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn attributes(&self) -> &[Attribute] {
+        &self.attributes
+    }
+
+    pub fn set_attributes(&mut self, attributes: Vec<Attribute>) {
+        self.attributes = attributes;
+    }
+
+    pub fn type_parameters(&self) -> &[TypeParameter] {
+        &self.type_parameters
+    }
+
+    pub fn set_type_parameters(&mut self, type_parameters: Vec<TypeParameter>) {
+        self.type_parameters = type_parameters;
+    }
+
+    /// Namespace the type is declared in, `None` for the global namespace.
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    pub fn set_namespace(&mut self, namespace: Option<String>) {
+        for chunk in &mut self.nested {
+            chunk.set_namespace(namespace.clone());
+        }
+        self.namespace = namespace;
+    }
+
+    pub fn variables(&self) -> &[Variable] {
+        &self.variables
+    }
+
+    pub fn properties(&self) -> &[Property] {
+        &self.properties
+    }
+
+    pub fn constructors(&self) -> &[Constructor] {
+        &self.constructors
+    }
+
+    pub fn static_constructor(&self) -> Option<&Constructor> {
+        self.static_constructor.as_ref()
+    }
+
+    pub fn methods(&self) -> &[Method] {
+        &self.methods
+    }
+
+    pub fn add_nested(&mut self, chunk: Chunk) {
+        self.nested.push(chunk);
+    }
+
+    pub fn nested(&self) -> &[Chunk] {
+        &self.nested
+    }
+
+    pub fn nested_mut(&mut self) -> &mut [Chunk] {
+        &mut self.nested
+    }
+
+    /// Every member in declaration order.
+    pub fn members(&self) -> Vec<Member<'_>> {
+        let mut res = Vec::new();
+        res.extend(self.variables.iter().map(Member::Variable));
+        res.extend(self.properties.iter().map(Member::Property));
+        res.extend(self.constructors.iter().map(Member::Constructor));
+        res.extend(self.static_constructor.iter().map(Member::Constructor));
+        res.extend(self.methods.iter().map(Member::Method));
+        res.extend(self.nested.iter().map(Member::Nested));
+        res.sort_by_key(|x| x.span().start());
+        res
+    }
+
+    /// Every type referenced by the struct itself, excluding nested chunks,
+    /// grouped with the type parameters it may refer to. The struct's own come
+    /// first, followed by one group per method.
+    pub fn scoped_types(&self) -> Vec<(Vec<String>, Vec<&Type>)> {
+        let generics = names(&self.type_parameters);
+        let mut res = self.base.iter().collect::<Vec<_>>();
+        res.extend(self.type_parameters.iter().flat_map(|x| x.types()));
+        res.extend(self.variables.iter().flat_map(|x| x.types()));
+        res.extend(self.properties.iter().flat_map(|x| x.types()));
+        res.extend(
+            self.constructors
+                .iter()
+                .chain(self.static_constructor.as_ref())
+                .flat_map(|x| x.types()),
+        );
+        let mut groups = vec![(generics, res)];
+        groups.extend(self.methods.iter().map(|x| x.scoped_types()));
+        groups
+    }
+
+    /// Same as `scoped_types`, for rewriting the types in place.
+    pub fn scoped_types_mut(&mut self) -> Vec<(Vec<String>, Vec<&mut Type>)> {
+        let generics = names(&self.type_parameters);
+        let mut res = self.base.iter_mut().collect::<Vec<_>>();
+        res.extend(self.type_parameters.iter_mut().flat_map(|x| x.types_mut()));
+        res.extend(self.variables.iter_mut().flat_map(|x| x.types_mut()));
+        res.extend(self.properties.iter_mut().flat_map(|x| x.types_mut()));
+        res.extend(
+            self.constructors
+                .iter_mut()
+                .chain(self.static_constructor.as_mut())
+                .flat_map(|x| x.types_mut()),
+        );
+        let mut groups = vec![(generics, res)];
+        groups.extend(self.methods.iter_mut().map(|x| x.scoped_types_mut()));
+        groups
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Interface {
+    name: String,
+    namespace: Option<String>,
+    modifiers: Vec<Modifier>,
+    attributes: Vec<Attribute>,
+    type_parameters: Vec<TypeParameter>,
+    base: Vec<Type>,
+    properties: Vec<Property>,
+    methods: Vec<Method>,
+    nested: Vec<Chunk>,
+    span: Span,
+}
+
+impl Interface {
+    pub fn new(name: String, modifiers: Vec<Modifier>, base: Vec<Type>) -> Self {
+        Self {
+            name,
+            modifiers,
+            base,
+            ..Default::default()
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn add_property(&mut self, property: Property) {
+        self.properties.push(property);
+    }
+
+    pub fn add_method(&mut self, method: Method) {
+        self.methods.push(method);
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn attributes(&self) -> &[Attribute] {
+        &self.attributes
+    }
+
+    pub fn set_attributes(&mut self, attributes: Vec<Attribute>) {
+        self.attributes = attributes;
+    }
+
+    pub fn type_parameters(&self) -> &[TypeParameter] {
+        &self.type_parameters
+    }
+
+    pub fn set_type_parameters(&mut self, type_parameters: Vec<TypeParameter>) {
+        self.type_parameters = type_parameters;
+    }
+
+    /// Namespace the type is declared in, `None` for the global namespace.
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    pub fn set_namespace(&mut self, namespace: Option<String>) {
+        for chunk in &mut self.nested {
+            chunk.set_namespace(namespace.clone());
+        }
+        self.namespace = namespace;
+    }
+
+    pub fn properties(&self) -> &[Property] {
+        &self.properties
+    }
+
+    pub fn methods(&self) -> &[Method] {
+        &self.methods
+    }
+
+    pub fn add_nested(&mut self, chunk: Chunk) {
+        self.nested.push(chunk);
+    }
+
+    pub fn nested(&self) -> &[Chunk] {
+        &self.nested
+    }
+
+    pub fn nested_mut(&mut self) -> &mut [Chunk] {
+        &mut self.nested
+    }
+
+    /// Every member in declaration order.
+    pub fn members(&self) -> Vec<Member<'_>> {
+        let mut res = Vec::new();
+        res.extend(self.properties.iter().map(Member::Property));
+        res.extend(self.methods.iter().map(Member::Method));
+        res.extend(self.nested.iter().map(Member::Nested));
+        res.sort_by_key(|x| x.span().start());
+        res
+    }
+
+    /// Every type referenced by the interface itself, excluding nested chunks,
+    /// grouped with the type parameters it may refer to. The interface's own come
+    /// first, followed by one group per method.
+    pub fn scoped_types(&self) -> Vec<(Vec<String>, Vec<&Type>)> {
+        let generics = names(&self.type_parameters);
+        let mut res = self.base.iter().collect::<Vec<_>>();
+        res.extend(self.type_parameters.iter().flat_map(|x| x.types()));
+        res.extend(self.properties.iter().flat_map(|x| x.types()));
+        let mut groups = vec![(generics, res)];
+        groups.extend(self.methods.iter().map(|x| x.scoped_types()));
+        groups
+    }
+
+    /// Same as `scoped_types`, for rewriting the types in place.
+    pub fn scoped_types_mut(&mut self) -> Vec<(Vec<String>, Vec<&mut Type>)> {
+        let generics = names(&self.type_parameters);
+        let mut res = self.base.iter_mut().collect::<Vec<_>>();
+        res.extend(self.type_parameters.iter_mut().flat_map(|x| x.types_mut()));
+        res.extend(self.properties.iter_mut().flat_map(|x| x.types_mut()));
+        let mut groups = vec![(generics, res)];
+        groups.extend(self.methods.iter_mut().map(|x| x.scoped_types_mut()));
+        groups
+    }
+}
+
+/// A member of a class, struct or interface, borrowed from its container.
+#[derive(Debug, Clone, Copy)]
+pub enum Member<'a> {
+    Variable(&'a Variable),
+    Property(&'a Property),
+    Constructor(&'a Constructor),
+    Finalizer(&'a Finalizer),
+    Method(&'a Method),
+    Nested(&'a Chunk),
+}
+
+impl Member<'_> {
+    pub fn span(&self) -> Span {
+        match self {
+            Member::Variable(x) => x.span(),
+            Member::Property(x) => x.span(),
+            Member::Constructor(x) => x.span(),
+            Member::Finalizer(x) => x.span(),
+            Member::Method(x) => x.span(),
+            Member::Nested(x) => x.span(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Chunk {
     Class(Class),
     Enum(Enum),
     Struct(Struct),
     Interface(Interface),
     Delegate(Method),
 }
+
+impl Chunk {
+    pub fn name(&self) -> &str {
+        match self {
+            Chunk::Class(x) => x.name(),
+            Chunk::Enum(x) => x.name(),
+            Chunk::Struct(x) => x.name(),
+            Chunk::Interface(x) => x.name(),
+            Chunk::Delegate(x) => x.name(),
+        }
+    }
+
+    pub fn namespace(&self) -> Option<&str> {
+        match self {
+            Chunk::Class(x) => x.namespace(),
+            Chunk::Enum(x) => x.namespace(),
+            Chunk::Struct(x) => x.namespace(),
+            Chunk::Interface(x) => x.namespace(),
+            Chunk::Delegate(x) => x.namespace(),
+        }
+    }
+
+    pub fn set_namespace(&mut self, namespace: Option<String>) {
+        match self {
+            Chunk::Class(x) => x.set_namespace(namespace),
+            Chunk::Enum(x) => x.set_namespace(namespace),
+            Chunk::Struct(x) => x.set_namespace(namespace),
+            Chunk::Interface(x) => x.set_namespace(namespace),
+            Chunk::Delegate(x) => x.set_namespace(namespace),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Chunk::Class(x) => x.span(),
+            Chunk::Enum(x) => x.span(),
+            Chunk::Struct(x) => x.span(),
+            Chunk::Interface(x) => x.span(),
+            Chunk::Delegate(x) => x.span(),
+        }
+    }
+
+    /// Name including the namespace, e.g. `Game.UI.Outer.Inner`.
+    pub fn full_name(&self) -> String {
+        match self.namespace() {
+            Some(namespace) => format!("{}.{}", namespace, self.name()),
+            None => self.name().to_string(),
+        }
+    }
+
+    pub fn nested(&self) -> &[Chunk] {
+        match self {
+            Chunk::Class(x) => x.nested(),
+            Chunk::Struct(x) => x.nested(),
+            Chunk::Interface(x) => x.nested(),
+            Chunk::Enum(_) | Chunk::Delegate(_) => &[],
+        }
+    }
+
+    /// This chunk followed by every chunk nested in it, depth first.
+    pub fn flatten(&self) -> Vec<&Chunk> {
+        let mut res = vec![self];
+        for chunk in self.nested() {
+            res.extend(chunk.flatten());
+        }
+        res
+    }
+
+    pub fn nested_mut(&mut self) -> &mut [Chunk] {
+        match self {
+            Chunk::Class(x) => x.nested_mut(),
+            Chunk::Struct(x) => x.nested_mut(),
+            Chunk::Interface(x) => x.nested_mut(),
+            Chunk::Enum(_) | Chunk::Delegate(_) => &mut [],
+        }
+    }
+
+    pub fn type_parameters(&self) -> &[TypeParameter] {
+        match self {
+            Chunk::Class(x) => x.type_parameters(),
+            Chunk::Struct(x) => x.type_parameters(),
+            Chunk::Interface(x) => x.type_parameters(),
+            Chunk::Delegate(x) => x.type_parameters(),
+            Chunk::Enum(_) => &[],
+        }
+    }
+
+    /// Every type referenced by the chunk itself, excluding nested chunks,
+    /// grouped with the type parameters declared for it. Type parameters of
+    /// enclosing chunks are not included.
+    pub fn scoped_types(&self) -> Vec<(Vec<String>, Vec<&Type>)> {
+        match self {
+            Chunk::Class(x) => x.scoped_types(),
+            Chunk::Enum(x) => vec![(Vec::new(), x.types())],
+            Chunk::Struct(x) => x.scoped_types(),
+            Chunk::Interface(x) => x.scoped_types(),
+            Chunk::Delegate(x) => vec![x.scoped_types()],
+        }
+    }
+
+    /// Same as `scoped_types`, for rewriting the types in place.
+    pub fn scoped_types_mut(&mut self) -> Vec<(Vec<String>, Vec<&mut Type>)> {
+        match self {
+            Chunk::Class(x) => x.scoped_types_mut(),
+            Chunk::Enum(x) => vec![(Vec::new(), x.types_mut())],
+            Chunk::Struct(x) => x.scoped_types_mut(),
+            Chunk::Interface(x) => x.scoped_types_mut(),
+            Chunk::Delegate(x) => vec![x.scoped_types_mut()],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Using {
+    /// `using System.IO;`
+    Namespace(String),
+    /// `using static System.Math;`
+    Static(String),
+    /// `using Writer = System.IO.BinaryWriter;`
+    Alias(String, Type),
+}
+
+/// Everything extracted from a single source file.
+#[derive(Debug, Clone, Default)]
+pub struct CompilationUnit {
+    path: PathBuf,
+    /// Using directives with the span of the namespace block they are
+    /// declared in, `None` for those at the top of the file.
+    usings: Vec<(Using, Option<Span>)>,
+    chunks: Vec<Chunk>,
+}
+
+impl CompilationUnit {
+    pub fn new(path: PathBuf, usings: Vec<(Using, Option<Span>)>, chunks: Vec<Chunk>) -> Self {
+        CompilationUnit {
+            path,
+            usings,
+            chunks,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn usings(&self) -> &[(Using, Option<Span>)] {
+        &self.usings
+    }
+
+    /// Using directives in effect for a declaration at `span`.
+    pub fn usings_at(&self, span: Span) -> Vec<Using> {
+        self.usings
+            .iter()
+            .filter(|(_, block)| block.is_none_or(|x| x.contains(span)))
+            .map(|(x, _)| x.clone())
+            .collect()
+    }
+
+    pub fn chunks(&self) -> &[Chunk] {
+        &self.chunks
+    }
+
+    pub fn chunks_mut(&mut self) -> &mut [Chunk] {
+        &mut self.chunks
+    }
+}
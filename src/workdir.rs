@@ -0,0 +1,79 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use rand::Rng;
+use rand::distr::Alphanumeric;
+
+/// Scratch directory holding the decompiled sources for a single run.
+///
+/// Directories created by `WorkDir::create` are removed on drop unless `keep`
+/// is set, so they are cleaned up whether the run succeeds or bails out with
+/// an error. Directories passed in by the user are never removed.
+#[derive(Debug)]
+pub struct WorkDir {
+    path: PathBuf,
+    owned: bool,
+    keep: bool,
+}
+
+impl WorkDir {
+    pub fn create(keep: bool) -> Result<Self> {
+        let id = rand::rng()
+            .sample_iter(Alphanumeric)
+            .map(|x| x as char)
+            .take(16)
+            .collect::<String>();
+        let path = env::temp_dir().join("tsdsp").join(id);
+        fs::create_dir_all(&path)
+            .with_context(|| format!("Could not create work directory {:?}", path))?;
+
+        Ok(WorkDir {
+            path: path.canonicalize()?,
+            owned: true,
+            keep,
+        })
+    }
+
+    pub fn reuse(path: &Path) -> Result<Self> {
+        fs::create_dir_all(path)
+            .with_context(|| format!("Could not create work directory {:?}", path))?;
+
+        Ok(WorkDir {
+            path: path.canonicalize()?,
+            owned: false,
+            keep: true,
+        })
+    }
+
+    pub fn open(path: Option<&Path>, keep: bool) -> Result<Self> {
+        match path {
+            Some(path) => Self::reuse(path),
+            None => Self::create(keep),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the directory already holds sources from a previous run.
+    pub fn is_populated(&self) -> bool {
+        fs::read_dir(&self.path)
+            .map(|mut x| x.next().is_some())
+            .unwrap_or(false)
+    }
+}
+
+impl Drop for WorkDir {
+    fn drop(&mut self) {
+        if self.owned
+            && !self.keep
+            && let Err(e) = fs::remove_dir_all(&self.path)
+        {
+            eprintln!("Could not remove work directory {:?}: {}", self.path, e);
+        }
+    }
+}